@@ -1,12 +1,77 @@
+/// Attributes applied to generated types, matched by fully-qualified proto path
+const TYPE_ATTRIBUTES: &[(&str, &str)] = &[
+    // Make every generated message and enum serializable
+    (
+        ".waypoint",
+        "#[derive(serde::Serialize, serde::Deserialize)]",
+    ),
+    (
+        ".google.protobuf",
+        "#[derive(serde::Serialize, serde::Deserialize)]",
+    ),
+];
+
+/// Attributes applied to generated messages only, since serde rejects them on
+/// enums
+const MESSAGE_ATTRIBUTES: &[(&str, &str)] = &[
+    // Treat missing fields as their proto3 defaults when deserializing
+    (".waypoint", "#[serde(default)]"),
+    (".google.protobuf", "#[serde(default)]"),
+];
+
+/// Serde attributes applied to individual generated fields, so the serialized
+/// form of the proto types matches the documents Meilisearch expects
+const FIELD_ATTRIBUTES: &[(&str, &str)] = &[
+    (
+        ".waypoint.meilisearch.UserProfile.geo",
+        "#[serde(rename = \"_geo\", skip_serializing_if = \"Option::is_none\")]",
+    ),
+    (
+        ".waypoint.meilisearch.EmbedderSettings.document_template",
+        "#[serde(skip_serializing_if = \"Option::is_none\")]",
+    ),
+];
+
+/// Proto files compiled into the crate
+const PROTOS: &[&str] = &[
+    "proto/meilisearch/user_profile.proto",
+    "proto/meilisearch/settings.proto",
+    "proto/common/types.proto",
+];
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Rerun codegen whenever a proto changes; watching the directory as well
+    // picks up newly added or imported files
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=proto");
+    for proto in PROTOS {
+        println!("cargo:rerun-if-changed={}", proto);
+    }
+
+    let mut config = prost_build::Config::new();
+
+    // Generate well-known types such as `google.protobuf.Timestamp` in this
+    // crate instead of pulling in `prost-types`, so they get the same derives
+    // as our own messages. Generated code refers to them relative to the
+    // package, so a proto that imports one also needs a `google::protobuf`
+    // module at the crate root
+    config.compile_well_known_types();
+
+    // Use BTreeMap for map fields so iteration and serialization order is
+    // deterministic
+    config.btree_map(["."]);
+
+    for (path, attribute) in TYPE_ATTRIBUTES {
+        config.type_attribute(path, attribute);
+    }
+    for (path, attribute) in MESSAGE_ATTRIBUTES {
+        config.message_attribute(path, attribute);
+    }
+    for (path, attribute) in FIELD_ATTRIBUTES {
+        config.field_attribute(path, attribute);
+    }
+
     // Compile the proto files
-    prost_build::compile_protos(
-        &[
-            "proto/meilisearch/user_profile.proto",
-            "proto/meilisearch/settings.proto",
-            "proto/common/types.proto",
-        ],
-        &["proto"],
-    )?;
+    config.compile_protos(PROTOS, &["proto"])?;
     Ok(())
 }
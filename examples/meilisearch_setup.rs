@@ -17,26 +17,20 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Create some example user profiles
     let profiles = vec![
-        UserProfile {
-            id: "1".to_string(),
-            fid: 1,
-            display_name: Some("Alice".to_string()),
-            username: Some("alice".to_string()),
-            bio: Some("I love Farcaster!".to_string()),
-            twitter: Some("alice_twitter".to_string()),
-            updated_at: 1646092800,
-            ..Default::default()
-        },
-        UserProfile {
-            id: "2".to_string(),
-            fid: 2,
-            display_name: Some("Bob".to_string()),
-            username: Some("bob".to_string()),
-            bio: Some("Web3 enthusiast".to_string()),
-            github: Some("bob_github".to_string()),
-            updated_at: 1646179200,
-            ..Default::default()
-        },
+        UserProfile::builder(1)
+            .display_name("Alice")
+            .username("alice")
+            .bio("I love Farcaster!")
+            .twitter("alice_twitter")
+            .updated_at(1646092800)
+            .build(),
+        UserProfile::builder(2)
+            .display_name("Bob")
+            .username("bob")
+            .bio("Web3 enthusiast")
+            .github("bob_github")
+            .updated_at(1646179200)
+            .build(),
     ];
 
     // Add profiles to Meilisearch
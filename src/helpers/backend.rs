@@ -0,0 +1,51 @@
+//! Backend-agnostic interface for storing and searching user profiles
+
+use async_trait::async_trait;
+
+use crate::helpers::common::Fid;
+use crate::proto::meilisearch::UserProfile;
+
+/// Parameters for a profile search
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProfileQuery<'a> {
+    /// Full-text query; empty matches every profile
+    pub query: &'a str,
+    /// Only return profiles with one of these fids
+    pub fids: Option<&'a [Fid]>,
+    /// Maximum number of results
+    pub limit: Option<usize>,
+    /// Number of results to skip
+    pub offset: Option<usize>,
+}
+
+impl<'a> ProfileQuery<'a> {
+    /// Search for `query` with no filters
+    pub fn new(query: &'a str) -> Self {
+        Self {
+            query,
+            ..Self::default()
+        }
+    }
+}
+
+/// A store that can index and search user profiles
+///
+/// Every write completes before its method returns, so a following `search`
+/// observes it regardless of whether the backend indexes asynchronously.
+#[async_trait]
+pub trait ProfileSearchBackend: Send + Sync {
+    /// Error returned by backend operations
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Create or update the profile index and its settings
+    async fn apply_schema(&self) -> Result<(), Self::Error>;
+
+    /// Insert profiles, replacing any existing profile with the same id
+    async fn upsert(&self, profiles: &[UserProfile]) -> Result<(), Self::Error>;
+
+    /// Search profiles, most relevant first
+    async fn search(&self, query: &ProfileQuery<'_>) -> Result<Vec<UserProfile>, Self::Error>;
+
+    /// Delete profiles by id; ids that don't exist are ignored
+    async fn delete(&self, ids: &[&str]) -> Result<(), Self::Error>;
+}
@@ -0,0 +1,498 @@
+//! Bulk import and export of user profiles
+
+use std::io::{BufRead, Read, Write};
+#[cfg(feature = "parquet")]
+use std::sync::Arc;
+
+#[cfg(feature = "parquet")]
+use arrow_array::builder::{ListBuilder, StringBuilder};
+#[cfg(feature = "parquet")]
+use arrow_array::{ArrayRef, BooleanArray, Float64Array, RecordBatch, StringArray, UInt64Array};
+#[cfg(feature = "parquet")]
+use arrow_schema::{DataType, Field, Schema};
+use futures::TryStreamExt;
+use meilisearch_sdk::client::Client;
+#[cfg(feature = "parquet")]
+use parquet::arrow::ArrowWriter;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::helpers::meilisearch::{
+    add_user_profile_documents, check_document_fid, check_document_size, parse_timestamp,
+    user_profile_document_pages, DocumentGeo, MeilisearchSchemaError, UserProfileDocument,
+    DEFAULT_MAX_DOCUMENT_BYTES,
+};
+
+/// Number of documents fetched per page during export
+const EXPORT_PAGE_SIZE: usize = 1000;
+
+/// Separator between wallet addresses within a single CSV cell
+const WALLET_ADDRESS_SEPARATOR: char = ';';
+
+/// A user profile as a flat CSV row
+///
+/// Unlike `UserProfileDocument`, every column is always written so that rows
+/// stay aligned with the header: `None` becomes an empty cell, and an empty
+/// cell reads back as `None`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct UserProfileCsvRow {
+    id: String,
+    fid: u64,
+    display_name: Option<String>,
+    username: Option<String>,
+    pfp_url: Option<String>,
+    bio: Option<String>,
+    url: Option<String>,
+    location: Option<String>,
+    twitter: Option<String>,
+    github: Option<String>,
+    #[serde(default)]
+    updated_at: String,
+    geo_lat: Option<f64>,
+    geo_lng: Option<f64>,
+    #[serde(default)]
+    verified: bool,
+    #[serde(default)]
+    follower_count: u64,
+    #[serde(default)]
+    following_count: u64,
+    #[serde(default)]
+    created_at: String,
+    #[serde(default)]
+    account_type: Option<String>,
+    // Wallet addresses joined with `;`, empty when there are none
+    #[serde(default)]
+    wallet_addresses: String,
+}
+
+impl From<UserProfileDocument> for UserProfileCsvRow {
+    fn from(doc: UserProfileDocument) -> Self {
+        Self {
+            id: doc.id,
+            fid: doc.fid,
+            display_name: doc.display_name,
+            username: doc.username,
+            pfp_url: doc.pfp_url,
+            bio: doc.bio,
+            url: doc.url,
+            location: doc.location,
+            twitter: doc.twitter,
+            github: doc.github,
+            updated_at: doc.updated_at.to_string(),
+            geo_lat: doc.geo.map(|geo| geo.lat),
+            geo_lng: doc.geo.map(|geo| geo.lng),
+            verified: doc.verified,
+            follower_count: doc.follower_count,
+            following_count: doc.following_count,
+            created_at: doc.created_at.to_string(),
+            account_type: doc.account_type,
+            wallet_addresses: doc
+                .wallet_addresses
+                .join(&WALLET_ADDRESS_SEPARATOR.to_string()),
+        }
+    }
+}
+
+impl From<UserProfileCsvRow> for UserProfileDocument {
+    fn from(row: UserProfileCsvRow) -> Self {
+        Self {
+            id: row.id,
+            fid: row.fid,
+            display_name: row.display_name,
+            // Derived when the document is written
+            username_normalized: None,
+            username: row.username,
+            pfp_url: row.pfp_url,
+            bio: row.bio,
+            url: row.url,
+            location: row.location,
+            twitter: row.twitter,
+            github: row.github,
+            updated_at: parse_timestamp(&row.updated_at).unwrap_or(0),
+            // A location needs both coordinates
+            geo: match (row.geo_lat, row.geo_lng) {
+                (Some(lat), Some(lng)) => Some(DocumentGeo { lat, lng }),
+                _ => None,
+            },
+            verified: row.verified,
+            follower_count: row.follower_count,
+            following_count: row.following_count,
+            created_at: parse_timestamp(&row.created_at).unwrap_or(0),
+            account_type: row.account_type,
+            wallet_addresses: row
+                .wallet_addresses
+                .split(WALLET_ADDRESS_SEPARATOR)
+                .map(str::trim)
+                .filter(|address| !address.is_empty())
+                .map(str::to_string)
+                .collect(),
+            // Derived when the document is written
+            wallet_addresses_normalized: Vec::new(),
+            // A flat row has no room for unknown fields
+            extra: Default::default(),
+        }
+    }
+}
+
+/// Export every user profile as newline-delimited JSON
+///
+/// Each document is written as a single JSON object followed by `\n`. The
+/// writer is flushed after every page, and the number of documents written is
+/// returned.
+pub async fn export_user_profiles_jsonl<W: Write>(
+    client: &Client,
+    writer: &mut W,
+) -> Result<usize, MeilisearchSchemaError> {
+    let mut pages = Box::pin(user_profile_document_pages(client, EXPORT_PAGE_SIZE));
+    let mut count = 0;
+
+    while let Some(page) = pages.try_next().await? {
+        for doc in &page {
+            write_jsonl_line(writer, doc)?;
+        }
+        count += page.len();
+        writer.flush()?;
+    }
+
+    info!(count, "exported user profiles as JSON lines");
+    Ok(count)
+}
+
+/// Write a single document as one line of JSON
+fn write_jsonl_line<W: Write>(
+    writer: &mut W,
+    doc: &UserProfileDocument,
+) -> Result<(), MeilisearchSchemaError> {
+    serde_json::to_writer(&mut *writer, doc)
+        .map_err(|e| MeilisearchSchemaError::Conversion(e.to_string()))?;
+    writer.write_all(b"\n")?;
+    Ok(())
+}
+
+/// Check a parsed document against the limits of the write path, so that one
+/// bad record is skipped instead of failing the batch it would be sent in
+fn check_importable(doc: &UserProfileDocument) -> Result<(), String> {
+    check_document_fid(doc)
+        .and_then(|()| check_document_size(&doc.clone().normalized(), DEFAULT_MAX_DOCUMENT_BYTES))
+        .map_err(|e| e.to_string())
+}
+
+/// Import user profiles from newline-delimited JSON
+///
+/// Lines are parsed as `UserProfileDocument`s and submitted in batches of
+/// `batch_size` (a size of 0 is treated as 1). Blank lines are ignored. Lines
+/// that can't be read or parsed, or whose document has fid 0 or is larger than
+/// [`DEFAULT_MAX_DOCUMENT_BYTES`], are skipped and returned as
+/// `(line number, error)` pairs, with line numbers starting at 1; only a failed
+/// submission to Meilisearch aborts the import.
+pub async fn import_user_profiles_jsonl<R: BufRead>(
+    client: &Client,
+    reader: R,
+    batch_size: usize,
+) -> Result<Vec<(usize, String)>, MeilisearchSchemaError> {
+    let batch_size = batch_size.max(1);
+    let mut batch: Vec<UserProfileDocument> = Vec::with_capacity(batch_size);
+    let mut skipped = Vec::new();
+    let mut imported = 0;
+
+    for (i, line) in reader.lines().enumerate() {
+        let line_number = i + 1;
+
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                skipped.push((line_number, e.to_string()));
+                continue;
+            }
+        };
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let parsed = serde_json::from_str::<UserProfileDocument>(&line)
+            .map_err(|e| e.to_string())
+            .and_then(|doc| check_importable(&doc).map(|()| doc));
+        match parsed {
+            Ok(doc) => batch.push(doc),
+            Err(e) => {
+                warn!(line = line_number, error = %e, "skipping user profile");
+                skipped.push((line_number, e));
+            }
+        }
+
+        if batch.len() >= batch_size {
+            add_user_profile_documents(client, &batch).await?;
+            imported += batch.len();
+            batch.clear();
+        }
+    }
+
+    if !batch.is_empty() {
+        add_user_profile_documents(client, &batch).await?;
+        imported += batch.len();
+    }
+
+    info!(
+        imported,
+        skipped = skipped.len(),
+        "imported user profiles from JSON lines"
+    );
+    Ok(skipped)
+}
+
+/// Export every user profile as CSV
+///
+/// A header row is written first, followed by one row per document. Optional
+/// fields that are unset become empty cells. Returns the number of profiles
+/// written.
+pub async fn export_user_profiles_csv<W: Write>(
+    client: &Client,
+    writer: &mut W,
+) -> Result<usize, MeilisearchSchemaError> {
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    let mut pages = Box::pin(user_profile_document_pages(client, EXPORT_PAGE_SIZE));
+    let mut count = 0;
+
+    while let Some(page) = pages.try_next().await? {
+        count += page.len();
+        for doc in page {
+            csv_writer
+                .serialize(UserProfileCsvRow::from(doc))
+                .map_err(|e| MeilisearchSchemaError::Conversion(e.to_string()))?;
+        }
+        csv_writer.flush()?;
+    }
+
+    info!(count, "exported user profiles as CSV");
+    Ok(count)
+}
+
+/// Import user profiles from CSV
+///
+/// The first row must be a header naming the columns written by
+/// [`export_user_profiles_csv`]. Rows are submitted in batches of `batch_size`
+/// (a size of 0 is treated as 1). Rows that can't be parsed, or whose document
+/// has fid 0 or is larger than [`DEFAULT_MAX_DOCUMENT_BYTES`], are skipped and
+/// returned as `(line number, error)` pairs; only a failed submission to
+/// Meilisearch aborts the import.
+pub async fn import_user_profiles_csv<R: Read>(
+    client: &Client,
+    reader: R,
+    batch_size: usize,
+) -> Result<Vec<(usize, String)>, MeilisearchSchemaError> {
+    let batch_size = batch_size.max(1);
+    let mut csv_reader = csv::Reader::from_reader(reader);
+    let mut batch: Vec<UserProfileDocument> = Vec::with_capacity(batch_size);
+    let mut skipped = Vec::new();
+    let mut imported = 0;
+
+    let headers = csv_reader
+        .headers()
+        .map_err(|e| {
+            MeilisearchSchemaError::Conversion(format!("Failed to read CSV header: {}", e))
+        })?
+        .clone();
+
+    for (i, record) in csv_reader.records().enumerate() {
+        // Fall back to the record index (after the header) when there's no
+        // position to report
+        let fallback_line = i + 2;
+        let parsed = record
+            .and_then(|record| {
+                let line_number = record.position().map(|p| p.line() as usize);
+                let row = record.deserialize::<UserProfileCsvRow>(Some(&headers))?;
+                Ok((
+                    line_number.unwrap_or(fallback_line),
+                    UserProfileDocument::from(row),
+                ))
+            })
+            .map_err(|e| {
+                let line_number = e.position().map(|p| p.line() as usize);
+                (line_number.unwrap_or(fallback_line), e.to_string())
+            })
+            .and_then(|(line_number, doc)| match check_importable(&doc) {
+                Ok(()) => Ok(doc),
+                Err(e) => Err((line_number, e)),
+            });
+        match parsed {
+            Ok(doc) => batch.push(doc),
+            Err((line_number, e)) => {
+                warn!(line = line_number, error = %e, "skipping user profile");
+                skipped.push((line_number, e));
+            }
+        }
+
+        if batch.len() >= batch_size {
+            add_user_profile_documents(client, &batch).await?;
+            imported += batch.len();
+            batch.clear();
+        }
+    }
+
+    if !batch.is_empty() {
+        add_user_profile_documents(client, &batch).await?;
+        imported += batch.len();
+    }
+
+    info!(
+        imported,
+        skipped = skipped.len(),
+        "imported user profiles from CSV"
+    );
+    Ok(skipped)
+}
+
+/// Arrow schema of the Parquet export, one column per document field
+#[cfg(feature = "parquet")]
+fn parquet_schema() -> Schema {
+    let text = |name| Field::new(name, DataType::Utf8, true);
+    Schema::new(vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new("fid", DataType::UInt64, false),
+        text("display_name"),
+        text("username"),
+        text("pfp_url"),
+        text("bio"),
+        text("url"),
+        text("location"),
+        text("twitter"),
+        text("github"),
+        Field::new("updated_at", DataType::UInt64, false),
+        Field::new("geo_lat", DataType::Float64, true),
+        Field::new("geo_lng", DataType::Float64, true),
+        Field::new("verified", DataType::Boolean, false),
+        Field::new("follower_count", DataType::UInt64, false),
+        Field::new("following_count", DataType::UInt64, false),
+        Field::new("created_at", DataType::UInt64, false),
+        text("account_type"),
+        Field::new(
+            "wallet_addresses",
+            DataType::List(Arc::new(Field::new("item", DataType::Utf8, true))),
+            false,
+        ),
+    ])
+}
+
+/// Convert a page of documents into a record batch matching `parquet_schema`
+#[cfg(feature = "parquet")]
+fn parquet_record_batch(
+    schema: &Arc<Schema>,
+    docs: &[UserProfileDocument],
+) -> Result<RecordBatch, MeilisearchSchemaError> {
+    let text = |field: fn(&UserProfileDocument) -> &Option<String>| -> ArrayRef {
+        Arc::new(
+            docs.iter()
+                .map(|doc| field(doc).as_deref())
+                .collect::<StringArray>(),
+        )
+    };
+    let number = |field: fn(&UserProfileDocument) -> u64| -> ArrayRef {
+        Arc::new(docs.iter().map(field).collect::<UInt64Array>())
+    };
+    let coordinate = |field: fn(&DocumentGeo) -> f64| -> ArrayRef {
+        Arc::new(
+            docs.iter()
+                .map(|doc| doc.geo.as_ref().map(field))
+                .collect::<Float64Array>(),
+        )
+    };
+
+    let mut wallet_addresses = ListBuilder::new(StringBuilder::new());
+    for doc in docs {
+        for address in &doc.wallet_addresses {
+            wallet_addresses.values().append_value(address);
+        }
+        wallet_addresses.append(true);
+    }
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(
+            docs.iter().map(|doc| &doc.id),
+        )),
+        number(|doc| doc.fid),
+        text(|doc| &doc.display_name),
+        text(|doc| &doc.username),
+        text(|doc| &doc.pfp_url),
+        text(|doc| &doc.bio),
+        text(|doc| &doc.url),
+        text(|doc| &doc.location),
+        text(|doc| &doc.twitter),
+        text(|doc| &doc.github),
+        number(|doc| doc.updated_at),
+        coordinate(|geo| geo.lat),
+        coordinate(|geo| geo.lng),
+        Arc::new(
+            docs.iter()
+                .map(|doc| Some(doc.verified))
+                .collect::<BooleanArray>(),
+        ),
+        number(|doc| doc.follower_count),
+        number(|doc| doc.following_count),
+        number(|doc| doc.created_at),
+        text(|doc| &doc.account_type),
+        Arc::new(wallet_addresses.finish()),
+    ];
+
+    RecordBatch::try_new(Arc::clone(schema), columns)
+        .map_err(|e| MeilisearchSchemaError::Conversion(e.to_string()))
+}
+
+/// Export every user profile as a Parquet file
+///
+/// Columns mirror the document fields, with the location split into
+/// `geo_lat` and `geo_lng`. Unset optional fields are written as nulls. Each
+/// page of documents becomes one row group. Returns the number of profiles
+/// written.
+#[cfg(feature = "parquet")]
+pub async fn export_user_profiles_parquet<W: Write + Send>(
+    client: &Client,
+    writer: &mut W,
+) -> Result<usize, MeilisearchSchemaError> {
+    let schema = Arc::new(parquet_schema());
+    let mut parquet = ArrowWriter::try_new(writer, Arc::clone(&schema), None)
+        .map_err(|e| MeilisearchSchemaError::Conversion(e.to_string()))?;
+
+    let mut pages = Box::pin(user_profile_document_pages(client, EXPORT_PAGE_SIZE));
+    let mut count = 0;
+
+    while let Some(page) = pages.try_next().await? {
+        let batch = parquet_record_batch(&schema, &page)?;
+        parquet
+            .write(&batch)
+            .and_then(|()| parquet.flush())
+            .map_err(|e| MeilisearchSchemaError::Conversion(e.to_string()))?;
+        count += page.len();
+    }
+
+    // Closing writes the footer, without which the file can't be read
+    parquet
+        .close()
+        .map_err(|e| MeilisearchSchemaError::Conversion(e.to_string()))?;
+
+    info!(count, "exported user profiles as Parquet");
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::meilisearch::UserProfile;
+
+    #[test]
+    fn oversized_documents_are_not_importable() {
+        let mut doc = UserProfileDocument::from(UserProfile::example(1));
+        assert!(check_importable(&doc).is_ok());
+
+        doc.bio = Some("a".repeat(DEFAULT_MAX_DOCUMENT_BYTES));
+        let error = check_importable(&doc).unwrap_err();
+        assert!(error.contains("'1'"), "{}", error);
+    }
+
+    #[test]
+    fn documents_without_a_fid_are_not_importable() {
+        let doc: UserProfileDocument =
+            serde_json::from_value(serde_json::json!({"id": "1", "username": "alice"})).unwrap();
+        assert!(check_importable(&doc).is_err());
+    }
+}
@@ -0,0 +1,212 @@
+//! Helpers for working with the common proto types
+
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use thiserror::Error;
+
+use crate::proto::common::{Error as ErrorMessage, FarcasterId, Pagination, Timestamp};
+
+/// Error type for invalid common type values
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum CommonTypeError {
+    #[error("Invalid fid: {0}")]
+    InvalidFid(u64),
+
+    #[error("Invalid timestamp: {0}")]
+    InvalidTimestamp(String),
+
+    #[error("Invalid pagination: {0}")]
+    InvalidPagination(String),
+}
+
+/// A Farcaster fid that is known to be valid
+///
+/// Farcaster never assigns fid 0, so it can't be constructed from one. Use
+/// this instead of a raw `u64` wherever a fid comes from outside the crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Fid(u64);
+
+impl Fid {
+    /// Wrap a raw fid, rejecting 0
+    pub fn new(fid: u64) -> Result<Self, CommonTypeError> {
+        if fid == 0 {
+            Err(CommonTypeError::InvalidFid(fid))
+        } else {
+            Ok(Self(fid))
+        }
+    }
+
+    /// The raw fid
+    pub fn as_u64(&self) -> u64 {
+        self.0
+    }
+}
+
+impl TryFrom<u64> for Fid {
+    type Error = CommonTypeError;
+
+    fn try_from(fid: u64) -> Result<Self, Self::Error> {
+        Self::new(fid)
+    }
+}
+
+impl From<Fid> for u64 {
+    fn from(fid: Fid) -> Self {
+        fid.0
+    }
+}
+
+impl fmt::Display for Fid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FarcasterId {
+    /// Wrap a raw fid
+    pub fn from_u64(fid: u64) -> Self {
+        Self { fid }
+    }
+
+    /// The raw fid
+    pub fn as_u64(&self) -> u64 {
+        self.fid
+    }
+
+    /// Check that the fid is usable; Farcaster never assigns fid 0
+    pub fn validate(&self) -> Result<(), CommonTypeError> {
+        if self.fid == 0 {
+            Err(CommonTypeError::InvalidFid(self.fid))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl From<u64> for FarcasterId {
+    fn from(fid: u64) -> Self {
+        Self::from_u64(fid)
+    }
+}
+
+impl From<FarcasterId> for u64 {
+    fn from(id: FarcasterId) -> Self {
+        id.fid
+    }
+}
+
+impl fmt::Display for FarcasterId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.fid)
+    }
+}
+
+impl Timestamp {
+    /// The current time
+    pub fn now() -> Self {
+        Self::from(Utc::now())
+    }
+
+    /// Build a timestamp from milliseconds since the Unix epoch
+    pub fn from_millis(millis: i64) -> Self {
+        Self {
+            seconds: millis.div_euclid(1000),
+            nanos: (millis.rem_euclid(1000) * 1_000_000) as i32,
+        }
+    }
+
+    /// Milliseconds since the Unix epoch, truncating sub-millisecond precision
+    pub fn as_millis(&self) -> i64 {
+        self.seconds * 1000 + i64::from(self.nanos / 1_000_000)
+    }
+
+    /// Check that `nanos` is within a single second
+    pub fn validate(&self) -> Result<(), CommonTypeError> {
+        if (0..1_000_000_000).contains(&self.nanos) {
+            Ok(())
+        } else {
+            Err(CommonTypeError::InvalidTimestamp(format!(
+                "nanos must be in 0..1000000000, got {}",
+                self.nanos
+            )))
+        }
+    }
+}
+
+impl From<DateTime<Utc>> for Timestamp {
+    fn from(dt: DateTime<Utc>) -> Self {
+        Self {
+            seconds: dt.timestamp(),
+            nanos: dt.timestamp_subsec_nanos() as i32,
+        }
+    }
+}
+
+impl TryFrom<Timestamp> for DateTime<Utc> {
+    type Error = CommonTypeError;
+
+    fn try_from(ts: Timestamp) -> Result<Self, Self::Error> {
+        ts.validate()?;
+        DateTime::from_timestamp(ts.seconds, ts.nanos as u32).ok_or_else(|| {
+            CommonTypeError::InvalidTimestamp(format!("{} seconds is out of range", ts.seconds))
+        })
+    }
+}
+
+impl Pagination {
+    /// Describe one page of `total` results, deriving `has_more`
+    pub fn new(total: u32, limit: u32, offset: u32) -> Self {
+        Self {
+            total,
+            limit,
+            offset,
+            has_more: offset.saturating_add(limit) < total,
+        }
+    }
+
+    /// Check that `limit` is positive and `has_more` agrees with the counts
+    pub fn validate(&self) -> Result<(), CommonTypeError> {
+        if self.limit == 0 {
+            return Err(CommonTypeError::InvalidPagination(
+                "limit must be positive".to_string(),
+            ));
+        }
+        if self.has_more != (self.offset.saturating_add(self.limit) < self.total) {
+            return Err(CommonTypeError::InvalidPagination(format!(
+                "has_more is {} for offset {} and limit {} of {} total",
+                self.has_more, self.offset, self.limit, self.total
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl ErrorMessage {
+    /// Build an error message without details
+    pub fn new(code: u32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            details: String::new(),
+        }
+    }
+
+    /// Attach details to the error message
+    pub fn with_details(mut self, details: impl Into<String>) -> Self {
+        self.details = details.into();
+        self
+    }
+}
+
+impl fmt::Display for ErrorMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.code, self.message)?;
+        if !self.details.is_empty() {
+            write!(f, " ({})", self.details)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ErrorMessage {}
@@ -0,0 +1,533 @@
+//! Helpers for building Meilisearch filter expressions
+
+use std::{fmt, ops};
+
+use thiserror::Error;
+
+use crate::helpers::common::Fid;
+use crate::proto::meilisearch::AccountType;
+
+/// Error type for filters that Meilisearch would reject
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum FilterError {
+    #[error("Filter value is not a finite number: {0}")]
+    NonFiniteFloat(f64),
+
+    #[error("Invalid fid range: {min} is greater than {max}")]
+    InvalidFidRange { min: Fid, max: Fid },
+
+    #[error("Invalid coordinates: latitude {lat}, longitude {lng}")]
+    InvalidCoordinates { lat: f64, lng: f64 },
+}
+
+/// Check that a point is on the map, with latitude in `[-90, 90]` and
+/// longitude in `[-180, 180]`
+pub fn check_coordinates(lat: f64, lng: f64) -> Result<(), FilterError> {
+    if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lng) {
+        Ok(())
+    } else {
+        Err(FilterError::InvalidCoordinates { lat, lng })
+    }
+}
+
+/// Quote a string value for use in a Meilisearch filter expression
+///
+/// Backslashes and double quotes are escaped so that user input can never
+/// terminate the quoted value and inject additional filter clauses.
+pub fn quote_filter_value(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        if c == '"' || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Builder for user profile filter expressions
+///
+/// Each method adds a clause, and clauses are combined with `AND`. The
+/// rendered string can be passed directly to the search helpers.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProfileFilter {
+    clauses: Vec<String>,
+}
+
+impl ProfileFilter {
+    /// Create an empty filter
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Match profiles with the given fid
+    pub fn fid_eq(mut self, fid: Fid) -> Self {
+        self.clauses.push(format!("fid = {}", fid));
+        self
+    }
+
+    /// Match profiles whose fid is any of the given values
+    pub fn fid_in(mut self, fids: &[Fid]) -> Self {
+        let values: Vec<String> = fids.iter().map(|fid| fid.to_string()).collect();
+        self.clauses.push(format!("fid IN [{}]", values.join(", ")));
+        self
+    }
+
+    /// Match only verified profiles
+    pub fn verified_only(mut self) -> Self {
+        self.clauses.push("verified = true".to_string());
+        self
+    }
+
+    /// Match profiles of the given account type
+    ///
+    /// `Unspecified` matches profiles with no account type set.
+    pub fn account_type(mut self, account_type: AccountType) -> Self {
+        let expr = match account_type {
+            AccountType::Unspecified => !FilterExpr::exists("account_type"),
+            account_type => FilterExpr::eq("account_type", account_type.as_lowercase_str()),
+        };
+        self.clauses.push(expr.to_string());
+        self
+    }
+
+    /// Match profiles whose account was created before `secs` (epoch seconds)
+    pub fn created_before(mut self, secs: u64) -> Self {
+        self.clauses.push(format!("created_at < {}", secs));
+        self
+    }
+
+    /// Match profiles whose account was created after `secs` (epoch seconds)
+    pub fn created_after(mut self, secs: u64) -> Self {
+        self.clauses.push(format!("created_at > {}", secs));
+        self
+    }
+
+    /// Match profiles with `min <= fid <= max`
+    ///
+    /// Fails if `min` is greater than `max`, which would match nothing.
+    pub fn fid_range(mut self, min: Fid, max: Fid) -> Result<Self, FilterError> {
+        if min > max {
+            return Err(FilterError::InvalidFidRange { min, max });
+        }
+        self.clauses.push(format!("fid {} TO {}", min, max));
+        Ok(self)
+    }
+
+    /// Match profiles located within `radius_m` meters of a point
+    ///
+    /// Fails if the point is off the map; see [`check_coordinates`].
+    pub fn within_radius(mut self, lat: f64, lng: f64, radius_m: u64) -> Result<Self, FilterError> {
+        check_coordinates(lat, lng)?;
+        self.clauses
+            .push(format!("_geoRadius({}, {}, {})", lat, lng, radius_m));
+        Ok(self)
+    }
+
+    /// Add an arbitrary filter expression as a clause
+    pub fn expr(mut self, expr: FilterExpr) -> Self {
+        self.clauses.push(expr.to_string());
+        self
+    }
+
+    /// Whether no clauses have been added
+    pub fn is_empty(&self) -> bool {
+        self.clauses.is_empty()
+    }
+
+    /// Render the filter as a Meilisearch filter string
+    pub fn build(&self) -> String {
+        self.clauses
+            .iter()
+            .map(|clause| format!("({})", clause))
+            .collect::<Vec<_>>()
+            .join(" AND ")
+    }
+}
+
+impl fmt::Display for ProfileFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.build())
+    }
+}
+
+/// A float that is neither NaN nor infinite, which Meilisearch can't parse
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct FiniteFloat(f64);
+
+impl FiniteFloat {
+    /// Wrap a float, rejecting NaN and infinities
+    pub fn new(value: f64) -> Result<Self, FilterError> {
+        if value.is_finite() {
+            Ok(Self(value))
+        } else {
+            Err(FilterError::NonFiniteFloat(value))
+        }
+    }
+
+    /// The wrapped float
+    pub fn get(&self) -> f64 {
+        self.0
+    }
+}
+
+impl fmt::Display for FiniteFloat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A value compared against a field in a [`FilterExpr`]
+///
+/// Floats go through [`FiniteFloat`], so build them with
+/// `FilterValue::try_from(f64)`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterValue {
+    String(String),
+    Int(i64),
+    UInt(u64),
+    Float(FiniteFloat),
+    Bool(bool),
+}
+
+impl fmt::Display for FilterValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::String(value) => f.write_str(&quote_filter_value(value)),
+            Self::Int(value) => write!(f, "{}", value),
+            Self::UInt(value) => write!(f, "{}", value),
+            Self::Float(value) => write!(f, "{}", value),
+            Self::Bool(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl From<&str> for FilterValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+impl From<String> for FilterValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<i64> for FilterValue {
+    fn from(value: i64) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl From<u64> for FilterValue {
+    fn from(value: u64) -> Self {
+        Self::UInt(value)
+    }
+}
+
+impl From<FiniteFloat> for FilterValue {
+    fn from(value: FiniteFloat) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl TryFrom<f64> for FilterValue {
+    type Error = FilterError;
+
+    fn try_from(value: f64) -> Result<Self, Self::Error> {
+        FiniteFloat::new(value).map(Self::Float)
+    }
+}
+
+impl From<bool> for FilterValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+/// A Meilisearch filter expression over arbitrary fields
+///
+/// Rendering with `to_string` quotes string values and any field name that
+/// isn't a plain identifier, and parenthesizes every compound expression, so
+/// the output is always valid filter syntax regardless of nesting.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterExpr {
+    Eq(String, FilterValue),
+    Ne(String, FilterValue),
+    Gt(String, FilterValue),
+    Gte(String, FilterValue),
+    Lt(String, FilterValue),
+    Lte(String, FilterValue),
+    In(String, Vec<FilterValue>),
+    Exists(String),
+    And(Box<FilterExpr>, Box<FilterExpr>),
+    Or(Box<FilterExpr>, Box<FilterExpr>),
+    Not(Box<FilterExpr>),
+}
+
+impl FilterExpr {
+    /// `field = value`
+    pub fn eq(field: impl Into<String>, value: impl Into<FilterValue>) -> Self {
+        Self::Eq(field.into(), value.into())
+    }
+
+    /// `field != value`
+    pub fn ne(field: impl Into<String>, value: impl Into<FilterValue>) -> Self {
+        Self::Ne(field.into(), value.into())
+    }
+
+    /// `field > value`
+    pub fn gt(field: impl Into<String>, value: impl Into<FilterValue>) -> Self {
+        Self::Gt(field.into(), value.into())
+    }
+
+    /// `field >= value`
+    pub fn gte(field: impl Into<String>, value: impl Into<FilterValue>) -> Self {
+        Self::Gte(field.into(), value.into())
+    }
+
+    /// `field < value`
+    pub fn lt(field: impl Into<String>, value: impl Into<FilterValue>) -> Self {
+        Self::Lt(field.into(), value.into())
+    }
+
+    /// `field <= value`
+    pub fn lte(field: impl Into<String>, value: impl Into<FilterValue>) -> Self {
+        Self::Lte(field.into(), value.into())
+    }
+
+    /// `field IN [values...]`
+    pub fn is_in<V: Into<FilterValue>>(
+        field: impl Into<String>,
+        values: impl IntoIterator<Item = V>,
+    ) -> Self {
+        Self::In(field.into(), values.into_iter().map(Into::into).collect())
+    }
+
+    /// `field EXISTS`
+    pub fn exists(field: impl Into<String>) -> Self {
+        Self::Exists(field.into())
+    }
+
+    /// Both this expression and `other` must match
+    pub fn and(self, other: FilterExpr) -> Self {
+        Self::And(Box::new(self), Box::new(other))
+    }
+
+    /// Either this expression or `other` must match
+    pub fn or(self, other: FilterExpr) -> Self {
+        Self::Or(Box::new(self), Box::new(other))
+    }
+}
+
+impl ops::Not for FilterExpr {
+    type Output = FilterExpr;
+
+    /// Negate an expression
+    fn not(self) -> Self::Output {
+        Self::Not(Box::new(self))
+    }
+}
+
+/// Render a field name, quoting it unless it's a plain identifier
+fn format_field(field: &str) -> String {
+    let plain = !field.is_empty()
+        && field
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+    if plain {
+        field.to_string()
+    } else {
+        quote_filter_value(field)
+    }
+}
+
+impl fmt::Display for FilterExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Eq(field, value) => write!(f, "{} = {}", format_field(field), value),
+            Self::Ne(field, value) => write!(f, "{} != {}", format_field(field), value),
+            Self::Gt(field, value) => write!(f, "{} > {}", format_field(field), value),
+            Self::Gte(field, value) => write!(f, "{} >= {}", format_field(field), value),
+            Self::Lt(field, value) => write!(f, "{} < {}", format_field(field), value),
+            Self::Lte(field, value) => write!(f, "{} <= {}", format_field(field), value),
+            Self::In(field, values) => {
+                let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+                write!(f, "{} IN [{}]", format_field(field), values.join(", "))
+            }
+            Self::Exists(field) => write!(f, "{} EXISTS", format_field(field)),
+            Self::And(left, right) => write!(f, "({}) AND ({})", left, right),
+            Self::Or(left, right) => write!(f, "({}) OR ({})", left, right),
+            Self::Not(expr) => write!(f, "NOT ({})", expr),
+        }
+    }
+}
+
+/// Tokens of a filter string, as far as [`filter_fields`] needs them
+enum Token<'a> {
+    Word(&'a str),
+    Quoted(String),
+    Open,
+    Close,
+    Other,
+}
+
+fn tokenize(filter: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut chars = filter.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            '"' | '\'' => {
+                let mut value = String::new();
+                while let Some((_, next)) = chars.next() {
+                    match next {
+                        '\\' => {
+                            if let Some((_, escaped)) = chars.next() {
+                                value.push(escaped);
+                            }
+                        }
+                        next if next == c => break,
+                        next => value.push(next),
+                    }
+                }
+                tokens.push(Token::Quoted(value));
+            }
+            '[' | ']' | ',' | '=' | '!' | '<' | '>' => tokens.push(Token::Other),
+            _ => {
+                let mut end = start + c.len_utf8();
+                while let Some(&(i, next)) = chars.peek() {
+                    if next.is_whitespace() || "()[],=!<>\"'".contains(next) {
+                        break;
+                    }
+                    end = i + next.len_utf8();
+                    chars.next();
+                }
+                tokens.push(Token::Word(&filter[start..end]));
+            }
+        }
+    }
+    tokens
+}
+
+/// List the attributes a filter string refers to, in order of appearance
+///
+/// Geo filters such as `_geoRadius(...)` are reported as `_geo`. This is a
+/// lightweight scan rather than a full parser, intended for checking a filter
+/// against the filterable attributes before sending it; malformed filters are
+/// left for Meilisearch to reject.
+pub fn filter_fields(filter: &str) -> Vec<String> {
+    let mut fields: Vec<String> = Vec::new();
+    let mut expect_field = true;
+    let mut tokens = tokenize(filter).into_iter();
+    while let Some(token) = tokens.next() {
+        let is_connective =
+            |word: &str| word.eq_ignore_ascii_case("AND") || word.eq_ignore_ascii_case("OR");
+        match token {
+            Token::Word(word) if is_connective(word) => expect_field = true,
+            Token::Word(word) if expect_field && word.eq_ignore_ascii_case("NOT") => {}
+            Token::Word(word) if expect_field && word.starts_with("_geo") => {
+                // Skip the geo function's arguments
+                let mut depth = 0;
+                for token in tokens.by_ref() {
+                    match token {
+                        Token::Open => depth += 1,
+                        Token::Close => {
+                            depth -= 1;
+                            if depth <= 0 {
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                push_unique(&mut fields, "_geo".to_string());
+                expect_field = false;
+            }
+            Token::Word(word) if expect_field => {
+                push_unique(&mut fields, word.to_string());
+                expect_field = false;
+            }
+            Token::Quoted(field) if expect_field => {
+                push_unique(&mut fields, field);
+                expect_field = false;
+            }
+            _ => {}
+        }
+    }
+    fields
+}
+
+fn push_unique(fields: &mut Vec<String>, field: String) {
+    if !fields.contains(&field) {
+        fields.push(field);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verified_only_renders_a_boolean_clause() {
+        let filter = ProfileFilter::new().verified_only();
+        assert_eq!(filter.build(), "(verified = true)");
+        assert_eq!(filter_fields(&filter.build()), ["verified"]);
+    }
+
+    #[test]
+    fn verified_only_combines_with_other_clauses() {
+        let filter = ProfileFilter::new()
+            .verified_only()
+            .fid_eq(Fid::new(7).unwrap());
+        assert_eq!(filter.build(), "(verified = true) AND (fid = 7)");
+        assert_eq!(filter_fields(&filter.build()), ["verified", "fid"]);
+    }
+
+    #[test]
+    fn non_finite_floats_are_rejected() {
+        for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert!(FilterValue::try_from(value).is_err(), "{} accepted", value);
+        }
+        let value = FilterValue::try_from(1.5).unwrap();
+        assert_eq!(FilterExpr::eq("score", value).to_string(), "score = 1.5");
+    }
+
+    #[test]
+    fn fid_range_rejects_min_above_max() {
+        let fid = |fid| Fid::new(fid).unwrap();
+        assert_eq!(
+            ProfileFilter::new()
+                .fid_range(fid(5), fid(9))
+                .unwrap()
+                .build(),
+            "(fid 5 TO 9)"
+        );
+        assert!(ProfileFilter::new().fid_range(fid(7), fid(7)).is_ok());
+        assert_eq!(
+            ProfileFilter::new().fid_range(fid(9), fid(5)),
+            Err(FilterError::InvalidFidRange {
+                min: fid(9),
+                max: fid(5)
+            })
+        );
+    }
+
+    #[test]
+    fn within_radius_rejects_points_off_the_map() {
+        assert!(ProfileFilter::new().within_radius(90.0, -180.0, 10).is_ok());
+        for (lat, lng) in [(90.5, 0.0), (-91.0, 0.0), (0.0, 180.5), (0.0, f64::NAN)] {
+            assert!(
+                ProfileFilter::new().within_radius(lat, lng, 10).is_err(),
+                "({}, {}) accepted",
+                lat,
+                lng
+            );
+        }
+    }
+}
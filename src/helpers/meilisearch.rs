@@ -1,12 +1,107 @@
 //! Helper functions for working with Meilisearch schemas
 
-use meilisearch_sdk::{client::Client, settings::Settings};
-use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock, PoisonError};
+use std::time::{Duration, Instant};
+
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+use meilisearch_sdk::{
+    client::Client,
+    documents::{DocumentDeletionQuery, DocumentsQuery},
+    errors::{Error as SdkError, ErrorCode, ErrorType, MeilisearchError},
+    indexes::IndexesQuery,
+    key::{Action, KeyBuilder},
+    request::{HttpClient, Method},
+    reqwest::ReqwestClient,
+    search::{MatchingStrategies, SearchResult, Selectors},
+    settings::{
+        FacetingSettings, MinWordSizeForTypos, PaginationSetting, Settings, TypoToleranceSettings,
+    },
+    task_info::TaskInfo,
+    tasks::{Task, TasksSearchQuery},
+};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
 use thiserror::Error;
-use tracing::{error, info};
+use time::OffsetDateTime;
+use tokio::time::MissedTickBehavior;
+use tracing::{error, info, warn};
 use chrono::{DateTime, Utc};
 
-use crate::proto::meilisearch::{UserProfile, UserProfileSchema};
+use crate::helpers::common::{CommonTypeError, Fid};
+use crate::helpers::filter::{filter_fields, FilterError, FilterExpr, ProfileFilter};
+use crate::helpers::schema::SchemaDefinition;
+use crate::proto::meilisearch::user_profile_schema::{
+    FilterableAttributes, IndexSettings, RankingRules, SearchableAttributes, SortableAttributes,
+};
+use crate::proto::meilisearch::{
+    AccountType, EmbedderSettings, GeoPoint, UserProfile, UserProfileSchema,
+};
+
+/// Maximum number of profiles returned for a single username
+const MAX_USERNAME_MATCHES: usize = 100;
+
+/// Maximum number of profiles returned for a single wallet address
+const MAX_WALLET_MATCHES: usize = 100;
+
+/// Number of fids per `fid IN [...]` filter, keeping filters well under
+/// Meilisearch's size limits
+const FID_FILTER_CHUNK_SIZE: usize = 500;
+
+/// Number of ids per `id IN [...]` filter, for the same reason
+const ID_FILTER_CHUNK_SIZE: usize = 500;
+
+/// How long `await_task` waits for a task before giving up
+pub const DEFAULT_TASK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Maximum number of results returned by `autocomplete_usernames`
+pub const MAX_AUTOCOMPLETE_LIMIT: usize = 20;
+
+/// Version of the schema returned by `get_user_profile_schema`
+///
+/// - 1: first versioned schema
+/// - 2: `verified` is filterable
+/// - 3: `follower_count` and `following_count` are sortable
+/// - 4: `id` is filterable, so searches can override `distinct`
+/// - 5: `created_at` is filterable and sortable
+/// - 6: typos are not tolerated on `username`
+/// - 7: `account_type` is filterable
+/// - 8: `updated_at` is filterable
+/// - 9: `verified` is sortable
+/// - 10: `wallet_addresses_normalized` is filterable
+pub const USER_PROFILE_SCHEMA_VERSION: u32 = 10;
+
+/// Meilisearch's default shortest word that may match with one typo
+const DEFAULT_MIN_WORD_SIZE_FOR_ONE_TYPO: u32 = 5;
+
+/// Meilisearch's default shortest word that may match with two typos
+const DEFAULT_MIN_WORD_SIZE_FOR_TWO_TYPOS: u32 = 9;
+
+/// Maximum number of candidates returned by `find_duplicate_profiles`
+pub const MAX_DUPLICATE_CANDIDATES: usize = 50;
+
+/// Number of documents `estimate_index_size` serializes to measure
+pub const SIZE_ESTIMATE_SAMPLE: usize = 100;
+
+/// Number of indexes fetched per request by `list_profile_indexes`
+const INDEX_LIST_PAGE_SIZE: usize = 100;
+
+/// How often `wait_until_indexed` checks whether indexing has finished
+pub const INDEXING_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How often `apply_user_profile_schema_when_ready` checks server health
+const HEALTH_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Index holding one version document per schema-managed index
+///
+/// Kept separate from the indexes it describes so the version records never
+/// show up in search results.
+pub const SCHEMA_VERSIONS_INDEX: &str = "schema_versions";
+
+/// Largest serialized document `add_user_profile_documents` will submit
+pub const DEFAULT_MAX_DOCUMENT_BYTES: usize = 1024 * 1024;
 
 /// Error type for Meilisearch operations
 #[derive(Error, Debug)]
@@ -14,24 +109,116 @@ pub enum MeilisearchSchemaError {
     #[error("Meilisearch client error: {0}")]
     Client(String),
 
-    #[error("Failed to apply schema: {0}")]
-    Schema(String),
+    #[error("Meilisearch client error: {0}")]
+    Sdk(#[from] SdkError),
+
+    /// The schema or a request was rejected, or a task failed while
+    /// Meilisearch processed it, in which case `task` holds the server's
+    /// error details
+    #[error("Failed to apply schema: {message}")]
+    Schema {
+        message: String,
+        task: Option<TaskError>,
+    },
 
     #[error("Failed to convert document: {0}")]
     Conversion(String),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    InvalidValue(#[from] CommonTypeError),
+
+    #[error(transparent)]
+    InvalidFilter(#[from] FilterError),
+
+    /// The server didn't report itself healthy before the deadline
+    #[error("Meilisearch was not available within {waited_ms}ms")]
+    Unavailable { waited_ms: u64 },
+}
+
+/// The error Meilisearch reported for a failed task
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskError {
+    pub task_uid: u32,
+    pub code: ErrorCode,
+    pub error_type: ErrorType,
+    pub message: String,
+    pub link: String,
+}
+
+impl MeilisearchSchemaError {
+    /// A `Schema` error that didn't come from a task
+    pub fn schema(message: impl Into<String>) -> Self {
+        Self::Schema {
+            message: message.into(),
+            task: None,
+        }
+    }
+
+    /// A `Schema` error for a task that failed on the server
+    fn failed_task(task_uid: u32, error: MeilisearchError) -> Self {
+        let task = TaskError {
+            task_uid,
+            code: error.error_code,
+            error_type: error.error_type,
+            message: error.error_message,
+            link: error.error_link,
+        };
+        Self::Schema {
+            message: format!(
+                "Task {} failed: {} (code: {}, type: {}, link: {})",
+                task.task_uid, task.message, task.code, task.error_type, task.link
+            ),
+            task: Some(task),
+        }
+    }
+
+    /// The server's error details, if this error is a failed task
+    pub fn task_error(&self) -> Option<&TaskError> {
+        match self {
+            Self::Schema { task, .. } => task.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// The Meilisearch error code, if this error came from the server
+    pub fn meilisearch_code(&self) -> Option<&ErrorCode> {
+        match self {
+            Self::Sdk(SdkError::Meilisearch(e)) => Some(&e.error_code),
+            _ => self.task_error().map(|task| &task.code),
+        }
+    }
 }
 
 
 /// Convert from generated proto type to a Serde-friendly type
+///
+/// When searching with `attributes_to_retrieve`, `id` must always be
+/// retrieved. Any other field may be omitted: `fid` reads back as 0, optional
+/// fields as `None`, and `updated_at` as 0.
+///
+/// Documents written by other services may use camelCase keys, so the
+/// camelCase spelling of each multi-word field is also accepted on read.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct UserProfileDocument {
     pub id: String,
+    #[serde(default)]
     pub fid: u64,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(alias = "displayName", skip_serializing_if = "Option::is_none")]
     pub display_name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub username: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    // Derived from `username` for case-insensitive exact lookups; never
+    // converted back into the proto
+    #[serde(
+        alias = "usernameNormalized",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub username_normalized: Option<String>,
+    #[serde(alias = "pfpUrl", skip_serializing_if = "Option::is_none")]
     pub pfp_url: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub bio: Option<String>,
@@ -43,289 +230,4050 @@ pub struct UserProfileDocument {
     pub twitter: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub github: Option<String>,
-    // Just make this a String to accept whatever Meilisearch sends us
+    // Stored as epoch seconds so Meilisearch sorts and filters it numerically;
+    // older documents holding a string timestamp are still read back
+    #[serde(
+        alias = "updatedAt",
+        default,
+        deserialize_with = "deserialize_timestamp"
+    )]
+    pub updated_at: u64,
+    // Meilisearch only geo-indexes documents that have a `_geo` key
+    #[serde(rename = "_geo", default, skip_serializing_if = "Option::is_none")]
+    pub geo: Option<DocumentGeo>,
+    // Added in schema version 2; documents indexed before then read as false
+    // and are left out of `verified = true` filters until they're re-added
     #[serde(default)]
-    pub updated_at: String,
+    pub verified: bool,
+    #[serde(alias = "followerCount", default)]
+    pub follower_count: u64,
+    #[serde(alias = "followingCount", default)]
+    pub following_count: u64,
+    // Account creation time in epoch seconds, parsed like `updated_at`; 0 when
+    // unknown
+    #[serde(
+        alias = "createdAt",
+        default,
+        deserialize_with = "deserialize_timestamp"
+    )]
+    pub created_at: u64,
+    // Lowercase `AccountType` name such as `bot`; unset for `Unspecified`, and
+    // unrecognized names read back as `Unspecified`
+    #[serde(
+        alias = "accountType",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub account_type: Option<String>,
+    // Connected wallet addresses as given, keeping any checksum casing
+    #[serde(
+        alias = "walletAddresses",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub wallet_addresses: Vec<String>,
+    // Derived from `wallet_addresses` by lowercasing, so that filters match
+    // regardless of checksum casing; never converted back into the proto
+    #[serde(
+        alias = "walletAddressesNormalized",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub wallet_addresses_normalized: Vec<String>,
+    // Fields this struct doesn't know about, such as ones added by another
+    // producer, kept so that reading and rewriting a document preserves them.
+    // Keys starting with `_` are dropped, as Meilisearch adds those to search
+    // hits (`_rankingScore`, `_formatted`, ...) and they aren't document data.
+    // Never converted into the proto
+    #[serde(flatten, default, deserialize_with = "deserialize_extra")]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+impl UserProfileDocument {
+    /// Recompute the fields derived from others: `username_normalized` from
+    /// `username` and `wallet_addresses_normalized` from `wallet_addresses`
+    ///
+    /// Every write goes through this, so documents from dumps or other
+    /// producers that lack the derived fields can still be looked up.
+    pub fn normalized(mut self) -> Self {
+        self.username_normalized = self.username.as_deref().map(normalize_username);
+        self.wallet_addresses_normalized = self
+            .wallet_addresses
+            .iter()
+            .map(|address| normalize_wallet_address(address))
+            .collect();
+        self
+    }
+
+    /// Overlay the set fields of `partial` onto this document
+    ///
+    /// A field in `partial` replaces the current value only when it carries
+    /// data: `Some` with a non-empty string for optional fields, a non-zero
+    /// `fid`, timestamp or count, `Some` for `geo`, `true` for `verified`, or a
+    /// non-empty `wallet_addresses` list, which replaces the whole list. `None`
+    /// and empty values leave the current value in place, so a merge never
+    /// clears a field. Unknown fields in `partial` are added, replacing any with the
+    /// same name. `id` is never changed.
+    pub fn merge(mut self, partial: UserProfileDocument) -> Self {
+        fn overlay(current: &mut Option<String>, incoming: Option<String>) {
+            if let Some(value) = incoming.filter(|v| !v.is_empty()) {
+                *current = Some(value);
+            }
+        }
+
+        // Destructure without `..` so new fields must be given merge semantics
+        let UserProfileDocument {
+            id: _,
+            fid,
+            display_name,
+            username,
+            username_normalized: _,
+            pfp_url,
+            bio,
+            url,
+            location,
+            twitter,
+            github,
+            updated_at,
+            geo,
+            verified,
+            follower_count,
+            following_count,
+            created_at,
+            account_type,
+            wallet_addresses,
+            wallet_addresses_normalized: _,
+            extra,
+        } = partial;
+
+        if fid != 0 {
+            self.fid = fid;
+        }
+        overlay(&mut self.display_name, display_name);
+        overlay(&mut self.username, username);
+        overlay(&mut self.pfp_url, pfp_url);
+        overlay(&mut self.bio, bio);
+        overlay(&mut self.url, url);
+        overlay(&mut self.location, location);
+        overlay(&mut self.twitter, twitter);
+        overlay(&mut self.github, github);
+        if updated_at != 0 {
+            self.updated_at = updated_at;
+        }
+        if geo.is_some() {
+            self.geo = geo;
+        }
+        if verified {
+            self.verified = true;
+        }
+        if follower_count != 0 {
+            self.follower_count = follower_count;
+        }
+        if following_count != 0 {
+            self.following_count = following_count;
+        }
+        if created_at != 0 {
+            self.created_at = created_at;
+        }
+        overlay(&mut self.account_type, account_type);
+        if !wallet_addresses.is_empty() {
+            self.wallet_addresses = wallet_addresses;
+        }
+        self.extra.extend(extra);
+
+        self.normalized()
+    }
+}
+
+/// Coordinates in the shape Meilisearch expects for `_geo`
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct DocumentGeo {
+    pub lat: f64,
+    pub lng: f64,
+}
+
+impl From<GeoPoint> for DocumentGeo {
+    fn from(point: GeoPoint) -> Self {
+        Self {
+            lat: point.lat,
+            lng: point.lng,
+        }
+    }
+}
+
+impl From<DocumentGeo> for GeoPoint {
+    fn from(geo: DocumentGeo) -> Self {
+        Self {
+            lat: geo.lat,
+            lng: geo.lng,
+        }
+    }
+}
+
+/// Normalize a username for case-insensitive comparison
+pub fn normalize_username(username: &str) -> String {
+    username.to_lowercase()
+}
+
+/// Normalize a wallet address so that checksum casing doesn't affect matching
+pub fn normalize_wallet_address(address: &str) -> String {
+    address.to_lowercase()
+}
+
+/// Names of every field stored in a user profile document
+pub const USER_PROFILE_FIELDS: &[&str] = &[
+    "id",
+    "fid",
+    "display_name",
+    "username",
+    "username_normalized",
+    "pfp_url",
+    "bio",
+    "url",
+    "location",
+    "twitter",
+    "github",
+    "updated_at",
+    "_geo",
+    "verified",
+    "follower_count",
+    "following_count",
+    "created_at",
+    "account_type",
+    "wallet_addresses",
+    "wallet_addresses_normalized",
+];
+
+/// Built-in Meilisearch ranking rules
+pub const BUILTIN_RANKING_RULES: &[&str] = &[
+    "words",
+    "typo",
+    "proximity",
+    "attribute",
+    "sort",
+    "exactness",
+];
+
+/// A Meilisearch ranking rule
+///
+/// Displays as, and parses from, the keyword Meilisearch expects, such as
+/// `words` or `follower_count:desc`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RankingRule {
+    Words,
+    Typo,
+    Proximity,
+    Attribute,
+    Sort,
+    Exactness,
+    /// Rank lower values of a field first
+    Asc(String),
+    /// Rank higher values of a field first
+    Desc(String),
+}
+
+impl RankingRule {
+    /// The field a custom rule sorts on, or `None` for built-in rules
+    pub fn field(&self) -> Option<&str> {
+        match self {
+            Self::Asc(field) | Self::Desc(field) => Some(field),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RankingRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Words => f.write_str("words"),
+            Self::Typo => f.write_str("typo"),
+            Self::Proximity => f.write_str("proximity"),
+            Self::Attribute => f.write_str("attribute"),
+            Self::Sort => f.write_str("sort"),
+            Self::Exactness => f.write_str("exactness"),
+            Self::Asc(field) => write!(f, "{}:asc", field),
+            Self::Desc(field) => write!(f, "{}:desc", field),
+        }
+    }
+}
+
+impl FromStr for RankingRule {
+    type Err = MeilisearchSchemaError;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        match rule {
+            "words" => Ok(Self::Words),
+            "typo" => Ok(Self::Typo),
+            "proximity" => Ok(Self::Proximity),
+            "attribute" => Ok(Self::Attribute),
+            "sort" => Ok(Self::Sort),
+            "exactness" => Ok(Self::Exactness),
+            _ => match rule.rsplit_once(':') {
+                Some((field, "asc")) if !field.is_empty() => Ok(Self::Asc(field.to_string())),
+                Some((field, "desc")) if !field.is_empty() => Ok(Self::Desc(field.to_string())),
+                _ => Err(MeilisearchSchemaError::schema(format!(
+                    "Invalid ranking rule '{}': expected a built-in rule or 'field:asc'/'field:desc'",
+                    rule
+                ))),
+            },
+        }
+    }
 }
 
 impl From<UserProfile> for UserProfileDocument {
     fn from(proto: UserProfile) -> Self {
+        // Destructure without `..` so that adding a field to the proto fails to
+        // compile until it's mapped here, instead of being silently dropped
+        let UserProfile {
+            id,
+            fid,
+            display_name,
+            username,
+            pfp_url,
+            bio,
+            url,
+            location,
+            twitter,
+            github,
+            updated_at,
+            geo,
+            verified,
+            follower_count,
+            following_count,
+            created_at,
+            account_type,
+            wallet_addresses,
+        } = proto;
+
         Self {
-            id: proto.id,
-            fid: proto.fid,
-            display_name: proto.display_name,
-            username: proto.username,
-            pfp_url: proto.pfp_url,
-            bio: proto.bio,
-            url: proto.url,
-            location: proto.location,
-            twitter: proto.twitter,
-            github: proto.github,
-            updated_at: proto.updated_at.to_string(),
+            id,
+            fid,
+            display_name,
+            username_normalized: username.as_deref().map(normalize_username),
+            username,
+            pfp_url,
+            bio,
+            url,
+            location,
+            twitter,
+            github,
+            updated_at,
+            geo: geo.map(DocumentGeo::from),
+            verified,
+            follower_count,
+            following_count,
+            created_at,
+            account_type: match AccountType::try_from(account_type) {
+                Ok(AccountType::Unspecified) | Err(_) => None,
+                Ok(account_type) => Some(account_type.to_string()),
+            },
+            wallet_addresses_normalized: wallet_addresses
+                .iter()
+                .map(|address| normalize_wallet_address(address))
+                .collect(),
+            wallet_addresses,
+            extra: HashMap::new(),
         }
     }
 }
 
 impl From<UserProfileDocument> for UserProfile {
     fn from(doc: UserProfileDocument) -> Self {
-        // Convert the string timestamp to u64 if possible, or default to current time
-        let updated_at = doc.updated_at.parse::<u64>()
-            .or_else(|_| {
-                // Try to parse as ISO datetime with explicit format handling
-                if doc.updated_at.contains('T') && doc.updated_at.contains('+') {
-                    // Try standard RFC3339 format first
-                    DateTime::parse_from_rfc3339(&doc.updated_at)
-                        .map(|dt| dt.timestamp() as u64)
-                        .or_else(|_| {
-                            // Try with various precise formats that might be returned by MeiliSearch
-                            DateTime::parse_from_str(&doc.updated_at, "%Y-%m-%dT%H:%M:%S%.f%:z")
-                                .or_else(|_| DateTime::parse_from_str(&doc.updated_at, "%Y-%m-%dT%H:%M:%S%.f%z"))
-                                .or_else(|_| DateTime::parse_from_str(&doc.updated_at, "%Y-%m-%dT%H:%M:%S%:z"))
-                                .or_else(|_| DateTime::parse_from_str(&doc.updated_at, "%Y-%m-%dT%H:%M:%S%z"))
-                                .map(|dt| dt.timestamp() as u64)
-                        })
-                } else {
-                    // Not a timestamp format we recognize
-                    // Return a generic parse error since we can't construct one directly
-                    Err(DateTime::parse_from_rfc3339("invalid").unwrap_err())
-                }
-            })
-            .unwrap_or_else(|_| {
-                // Default to current time if parsing fails
-                Utc::now().timestamp() as u64
-            });
+        // Destructure without `..` so that adding a field to the document fails
+        // to compile until it's mapped here
+        let UserProfileDocument {
+            id,
+            fid,
+            display_name,
+            username,
+            username_normalized: _,
+            pfp_url,
+            bio,
+            url,
+            location,
+            twitter,
+            github,
+            updated_at,
+            geo,
+            verified,
+            follower_count,
+            following_count,
+            created_at,
+            account_type,
+            wallet_addresses,
+            wallet_addresses_normalized: _,
+            extra: _,
+        } = doc;
 
         Self {
-            id: doc.id,
-            fid: doc.fid,
-            display_name: doc.display_name,
-            username: doc.username,
-            pfp_url: doc.pfp_url,
-            bio: doc.bio,
-            url: doc.url,
-            location: doc.location,
-            twitter: doc.twitter,
-            github: doc.github,
+            id,
+            fid,
+            display_name,
+            username,
+            pfp_url,
+            bio,
+            url,
+            location,
+            twitter,
+            github,
             updated_at,
+            geo: geo.map(GeoPoint::from),
+            verified,
+            follower_count,
+            following_count,
+            created_at,
+            account_type: account_type
+                .as_deref()
+                .map_or(AccountType::Unspecified, AccountType::from)
+                .into(),
+            wallet_addresses,
         }
     }
 }
 
-/// Apply the user profile schema to Meilisearch
-pub async fn apply_user_profile_schema(client: &Client) -> Result<(), MeilisearchSchemaError> {
-    info!("Applying user profiles schema to Meilisearch");
+/// Convert fetched documents into proto profiles, consuming them
+pub fn documents_to_profiles(docs: Vec<UserProfileDocument>) -> Vec<UserProfile> {
+    docs.into_iter().map(UserProfile::from).collect()
+}
 
-    // Get the predefined schema
-    let schema = get_user_profile_schema();
+/// Convert proto profiles into the documents that get indexed
+pub fn profiles_to_documents(profiles: &[UserProfile]) -> Vec<UserProfileDocument> {
+    profiles
+        .iter()
+        .cloned()
+        .map(UserProfileDocument::from)
+        .collect()
+}
 
-    // Extract index settings
-    let index_settings = schema
-        .index
-        .ok_or_else(|| MeilisearchSchemaError::Schema("No index settings provided".to_string()))?;
+/// Parse a stored timestamp such as `updated_at` into epoch seconds
+///
+/// Accepts plain epoch seconds as well as RFC 3339 datetimes with any UTC
+/// offset, with or without fractional seconds (which are truncated). Returns
+/// `None` for anything else, including datetimes before the Unix epoch.
+pub fn parse_timestamp(value: &str) -> Option<u64> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(secs);
+    }
 
-    // Create the index if it doesn't exist
-    let index_name = &index_settings.name;
-    let primary_key = &index_settings.primary_key;
+    // Try standard RFC3339 format first, then the variants without a colon in
+    // the offset that might be returned by MeiliSearch
+    DateTime::parse_from_rfc3339(value)
+        .or_else(|_| DateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f%z"))
+        .or_else(|_| DateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%z"))
+        .ok()
+        .and_then(|dt| u64::try_from(dt.timestamp()).ok())
+}
 
-    // Create index
-    match client.create_index(index_name, Some(primary_key)).await {
-        Ok(task) => {
-            info!(
-                "Created index '{}' with primary key '{}', task ID: {}",
-                index_name, primary_key, task.task_uid
-            );
-        }
-        Err(e) => {
-            // If the error is that the index already exists, that's okay
-            if !e.to_string().contains("index_already_exists") {
-                return Err(MeilisearchSchemaError::Client(e.to_string()));
-            }
-        }
+/// Deserialize a stored timestamp as epoch seconds
+///
+/// Accepts a number, or a string in any format [`parse_timestamp`]
+/// understands. Missing, null and unparseable values read as 0.
+fn deserialize_timestamp<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StoredTimestamp {
+        Secs(u64),
+        Float(f64),
+        Text(String),
     }
 
-    // Configure settings
-    let mut settings = Settings::new();
+    let secs = match Option::<StoredTimestamp>::deserialize(deserializer)? {
+        Some(StoredTimestamp::Secs(secs)) => secs,
+        Some(StoredTimestamp::Float(secs)) if secs >= 0.0 => secs as u64,
+        Some(StoredTimestamp::Text(text)) => parse_timestamp(&text).unwrap_or(0),
+        _ => 0,
+    };
+    Ok(secs)
+}
 
-    // Searchable attributes
-    if let Some(searchable) = &schema.searchable {
-        let attrs: Vec<String> = searchable
-            .attributes
-            .iter()
-            .map(|s| s.to_string())
-            .collect();
-        settings = settings.with_searchable_attributes(attrs);
-    }
+/// Read the unknown fields of a document, leaving out Meilisearch's own
+/// `_`-prefixed keys
+///
+/// `_geo` is the only such key that is document data, and `geo` claims it
+/// before it gets here.
+fn deserialize_extra<'de, D>(
+    deserializer: D,
+) -> Result<HashMap<String, serde_json::Value>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let mut extra = HashMap::<String, serde_json::Value>::deserialize(deserializer)?;
+    extra.retain(|key, _| !key.starts_with('_'));
+    Ok(extra)
+}
 
-    // Ranking rules
-    if let Some(ranking) = &schema.ranking {
-        let rules: Vec<String> = ranking.rules.iter().map(|s| s.to_string()).collect();
-        settings = settings.with_ranking_rules(rules);
+impl From<UserProfile> for serde_json::Value {
+    fn from(profile: UserProfile) -> Self {
+        // Every field has a JSON representation (non-finite floats become
+        // `null`) and map keys are strings, so serialization can't fail
+        serde_json::to_value(UserProfileDocument::from(profile))
+            .expect("user profile documents always serialize to JSON")
     }
+}
 
-    // Distinct attribute
-    if !schema.distinct_attribute.is_empty() {
-        settings = settings.with_distinct_attribute(Some(schema.distinct_attribute.clone()));
+impl UserProfile {
+    /// Convert to a JSON object with the same shape as the indexed document
+    ///
+    /// Unset optional fields are omitted rather than written as `null`.
+    pub fn to_json(&self) -> serde_json::Value {
+        self.clone().into()
     }
 
-    // Filterable attributes
-    if let Some(filterable) = &schema.filterable {
-        let attrs: Vec<String> = filterable
-            .attributes
-            .iter()
-            .map(|s| s.to_string())
-            .collect();
-        settings = settings.with_filterable_attributes(attrs);
+    /// Parse a JSON object with the shape of an indexed document
+    pub fn from_json(value: serde_json::Value) -> Result<Self, MeilisearchSchemaError> {
+        serde_json::from_value::<UserProfileDocument>(value)
+            .map(Self::from)
+            .map_err(|e| MeilisearchSchemaError::Conversion(e.to_string()))
     }
+}
 
-    // Sortable attributes
-    if let Some(sortable) = &schema.sortable {
-        let attrs: Vec<String> = sortable.attributes.iter().map(|s| s.to_string()).collect();
-        settings = settings.with_sortable_attributes(attrs);
+/// Environment variable holding the Meilisearch URL
+pub const MEILI_URL_ENV: &str = "MEILI_URL";
+
+/// Environment variable holding the Meilisearch API key
+pub const MEILI_MASTER_KEY_ENV: &str = "MEILI_MASTER_KEY";
+
+/// Create a Meilisearch client after validating the server URL
+///
+/// The URL must be a non-empty absolute `http` or `https` URL.
+pub fn build_client(url: &str, api_key: Option<&str>) -> Result<Client, MeilisearchSchemaError> {
+    let url = url.trim();
+    if url.is_empty() {
+        return Err(MeilisearchSchemaError::Client(
+            "Meilisearch URL must not be empty".to_string(),
+        ));
     }
 
-    // Apply settings
-    let index = client.index(index_name);
-    match index.set_settings(&settings).await {
-        Ok(task) => {
-            info!(
-                "Applied settings to index '{}', task ID: {}",
-                index_name, task.task_uid
-            );
-            Ok(())
-        }
-        Err(e) => {
-            error!("Failed to apply settings to index '{}': {}", index_name, e);
-            Err(MeilisearchSchemaError::Client(e.to_string()))
-        }
+    let parsed = url::Url::parse(url).map_err(|e| {
+        MeilisearchSchemaError::Client(format!("Invalid Meilisearch URL '{}': {}", url, e))
+    })?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(MeilisearchSchemaError::Client(format!(
+            "Meilisearch URL '{}' must use http or https",
+            url
+        )));
     }
+
+    // The SDK appends paths to the host, so drop any trailing slash
+    Client::new(url.trim_end_matches('/'), api_key)
+        .map_err(|e| MeilisearchSchemaError::Client(e.to_string()))
 }
 
-/// Get the predefined user profile schema
-pub fn get_user_profile_schema() -> UserProfileSchema {
-    let mut schema = UserProfileSchema::default();
+/// Create a Meilisearch client from `MEILI_URL` and `MEILI_MASTER_KEY`
+///
+/// `MEILI_URL` is required; `MEILI_MASTER_KEY` is optional for servers running
+/// without authentication.
+pub fn build_client_from_env() -> Result<Client, MeilisearchSchemaError> {
+    let url = std::env::var(MEILI_URL_ENV)
+        .map_err(|_| MeilisearchSchemaError::Client(format!("{} is not set", MEILI_URL_ENV)))?;
+    let api_key = std::env::var(MEILI_MASTER_KEY_ENV).ok();
 
-    // Index settings
-    let index = crate::proto::meilisearch::user_profile_schema::IndexSettings {
-        name: "user_profiles".to_string(),
-        primary_key: "id".to_string(),
-    };
+    build_client(&url, api_key.as_deref())
+}
 
-    // Searchable attributes
-    let searchable = crate::proto::meilisearch::user_profile_schema::SearchableAttributes {
-        attributes: vec![
-            "username".to_string(),
-            "display_name".to_string(),
-            "bio".to_string(),
-            "location".to_string(),
-            "twitter".to_string(),
-            "github".to_string(),
-            "fid".to_string(),
-        ],
-    };
+/// Whether an SDK error reports that the index already exists
+pub fn is_index_already_exists(err: &SdkError) -> bool {
+    has_error_code(err, ErrorCode::IndexAlreadyExists)
+}
 
-    // Ranking rules
-    let ranking = crate::proto::meilisearch::user_profile_schema::RankingRules {
-        rules: vec![
-            "words".to_string(),
-            "typo".to_string(),
-            "proximity".to_string(),
-            "attribute".to_string(),
-            "sort".to_string(),
-            "exactness".to_string(),
-        ],
-    };
+/// Whether an SDK error reports that the document does not exist
+pub fn is_document_not_found(err: &SdkError) -> bool {
+    has_error_code(err, ErrorCode::DocumentNotFound)
+}
 
-    // Filterable attributes
-    let filterable = crate::proto::meilisearch::user_profile_schema::FilterableAttributes {
-        attributes: vec!["fid".to_string()],
-    };
+/// Whether an SDK error reports that the index does not exist
+pub fn is_index_not_found(err: &SdkError) -> bool {
+    has_error_code(err, ErrorCode::IndexNotFound)
+}
 
-    // Sortable attributes
-    let sortable = crate::proto::meilisearch::user_profile_schema::SortableAttributes {
-        attributes: vec!["fid".to_string(), "updated_at".to_string()],
-    };
+/// Whether an SDK error is a Meilisearch error with the given code
+fn has_error_code(err: &SdkError, code: ErrorCode) -> bool {
+    matches!(err, SdkError::Meilisearch(e) if e.error_code == code)
+}
 
-    // Set the fields
-    schema.index = Some(index);
-    schema.searchable = Some(searchable);
-    schema.ranking = Some(ranking);
-    schema.distinct_attribute = "username".to_string();
-    schema.filterable = Some(filterable);
-    schema.sortable = Some(sortable);
+/// Run a Meilisearch operation, failing with `Client("timeout")` if it takes
+/// longer than `timeout`
+///
+/// The SDK applies no request timeout of its own, so this keeps callers from
+/// hanging on a stalled server. The operation is dropped on timeout; anything
+/// it already enqueued on the server is not cancelled.
+pub async fn with_timeout<T, F>(
+    timeout: Duration,
+    operation: F,
+) -> Result<T, MeilisearchSchemaError>
+where
+    F: Future<Output = Result<T, MeilisearchSchemaError>>,
+{
+    match tokio::time::timeout(timeout, operation).await {
+        Ok(result) => result,
+        Err(_) => {
+            error!(
+                timeout_ms = timeout.as_millis() as u64,
+                "Meilisearch operation timed out"
+            );
+            Err(MeilisearchSchemaError::Client("timeout".to_string()))
+        }
+    }
+}
 
-    schema
+/// Apply the user profile schema to Meilisearch
+pub async fn apply_user_profile_schema(client: &Client) -> Result<(), MeilisearchSchemaError> {
+    // Get the predefined schema
+    apply_user_profile_schema_with(client, &get_user_profile_schema()).await
 }
 
-/// Create a batch of user profiles in Meilisearch
-pub async fn batch_create_user_profiles(
+/// Apply a variation of the user profile schema, such as one made with
+/// [`UserProfileSchemaBuilder`], after checking it with [`validate_schema`]
+pub async fn apply_user_profile_schema_with(
     client: &Client,
-    profiles: &[UserProfile],
+    schema: &UserProfileSchema,
 ) -> Result<(), MeilisearchSchemaError> {
-    // Convert proto profiles to Meilisearch documents
-    let documents: Vec<UserProfileDocument> = profiles
-        .iter()
-        .map(|p| UserProfileDocument::from(p.clone()))
-        .collect();
+    info!("applying user profiles schema to Meilisearch");
 
-    // Add documents to index
-    let index = client.index("user_profiles");
-    match index.add_or_update(&documents, Some("id")).await {
-        Ok(task) => {
-            info!(
-                "Added {} user profiles to Meilisearch, task ID: {}",
-                documents.len(),
-                task.task_uid
-            );
-            Ok(())
-        }
-        Err(e) => {
-            error!("Failed to add user profiles to Meilisearch: {}", e);
-            Err(MeilisearchSchemaError::Client(e.to_string()))
-        }
-    }
+    validate_schema(schema)?;
+    apply_schema(client, schema).await
 }
 
-/// Search for user profiles
-pub async fn search_user_profiles(
+/// [`apply_user_profile_schema`], giving up after `timeout`
+pub async fn apply_user_profile_schema_with_timeout(
     client: &Client,
-    query: &str,
-    limit: Option<usize>,
-    offset: Option<usize>,
-    filter: Option<&str>,
-) -> Result<Vec<UserProfile>, MeilisearchSchemaError> {
-    let index = client.index("user_profiles");
+    timeout: Duration,
+) -> Result<(), MeilisearchSchemaError> {
+    with_timeout(timeout, apply_user_profile_schema(client)).await
+}
 
-    // Create search query
-    let mut search = index.search();
-    search.with_query(query);
+/// Wait for the server to become healthy, then apply the user profile schema
+///
+/// Meant for startup, when Meilisearch may still be booting. Health is polled
+/// until the server reports `available`; if that doesn't happen within
+/// `timeout`, fails with [`MeilisearchSchemaError::Unavailable`] without
+/// touching the schema. The timeout only covers the wait, so once the server
+/// is up any error comes from [`apply_user_profile_schema`] itself.
+pub async fn apply_user_profile_schema_when_ready(
+    client: &Client,
+    timeout: Duration,
+) -> Result<(), MeilisearchSchemaError> {
+    let ready = async {
+        while !client.is_healthy().await {
+            tokio::time::sleep(HEALTH_POLL_INTERVAL).await;
+        }
+    };
 
-    if let Some(limit_val) = limit {
-        search.with_limit(limit_val);
+    if tokio::time::timeout(timeout, ready).await.is_err() {
+        let waited_ms = timeout.as_millis() as u64;
+        error!(timeout_ms = waited_ms, "Meilisearch never became available");
+        return Err(MeilisearchSchemaError::Unavailable { waited_ms });
     }
 
-    if let Some(offset_val) = offset {
-        search.with_offset(offset_val);
-    }
+    apply_user_profile_schema(client).await
+}
 
-    if let Some(filter_val) = filter {
-        search.with_filter(filter_val);
+/// Settings keys whose values Meilisearch treats as unordered sets
+const UNORDERED_SETTINGS: &[&str] = &[
+    "filterableAttributes",
+    "sortableAttributes",
+    "stopWords",
+    "dictionary",
+    "separatorTokens",
+    "nonSeparatorTokens",
+];
+
+/// Apply the user profile schema, changing only settings that differ from
+/// what the index already has
+///
+/// Any setting change makes Meilisearch reindex, so this fetches the current
+/// settings first and submits just the keys whose values differ, then waits
+/// for the update to finish and returns its task. Returns `Ok(None)` without
+/// submitting anything when the index is already up to date. If the index
+/// doesn't exist yet, the full schema is applied instead, and the task of its
+/// settings update is returned.
+/// Embedders aren't compared; use `apply_user_profile_schema` to update them.
+pub async fn apply_user_profile_schema_incremental(
+    client: &Client,
+) -> Result<Option<TaskInfo>, MeilisearchSchemaError> {
+    let (_, task) = apply_changed_user_profile_settings(client).await?;
+    Ok(task)
+}
+
+/// What [`ensure_user_profile_schema`] had to change
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchemaChangeReport {
+    /// Whether the index didn't exist and was created
+    pub index_created: bool,
+    /// Settings keys that were updated, such as `rankingRules`; every key the
+    /// schema defines when the index was created
+    pub settings_changed: Vec<String>,
+}
+
+impl SchemaChangeReport {
+    /// Whether the index already matched the schema
+    pub fn is_unchanged(&self) -> bool {
+        !self.index_created && self.settings_changed.is_empty()
     }
+}
 
-    // Execute search
-    match search.execute::<UserProfileDocument>().await {
-        Ok(results) => {
-            // Convert documents back to proto messages
-            let profiles = results
-                .hits
-                .into_iter()
-                .map(|hit| UserProfile::from(hit.result))
-                .collect();
+/// Make sure the user profiles index exists with the current schema
+///
+/// Works like [`apply_user_profile_schema_incremental`], but reports what
+/// was changed. Running it again right away reports no changes.
+pub async fn ensure_user_profile_schema(
+    client: &Client,
+) -> Result<SchemaChangeReport, MeilisearchSchemaError> {
+    let (report, _) = apply_changed_user_profile_settings(client).await?;
+    Ok(report)
+}
+
+/// Submit the user profile settings that differ from the live index, creating
+/// the index with the full schema if it doesn't exist
+async fn apply_changed_user_profile_settings(
+    client: &Client,
+) -> Result<(SchemaChangeReport, Option<TaskInfo>), MeilisearchSchemaError> {
+    let schema = get_user_profile_schema();
+    validate_schema(&schema)?;
 
-            Ok(profiles)
+    let index_settings = user_profile_index_settings()?;
+    let index = client.index(&index_settings.name);
+    let desired = user_profile_settings();
+    let current = match index.get_settings().await {
+        Ok(current) => current,
+        Err(e) if is_index_not_found(&e) => {
+            info!(index = %index_settings.name, "index not found, applying full schema");
+            // Waits for the settings like the branch below, so the report is
+            // only returned once a fresh index is fully configured
+            let tasks = apply_schema_tasks(client, &schema).await?;
+            let (_, settings_changed) = settings_changes(&Settings::new(), &desired)?;
+            let report = SchemaChangeReport {
+                index_created: true,
+                settings_changed,
+            };
+            return Ok((report, tasks.into_iter().next()));
         }
         Err(e) => {
-            error!("Failed to search user profiles: {}", e);
-            Err(MeilisearchSchemaError::Client(e.to_string()))
+            error!(index = %index_settings.name, error = %e, "failed to get index settings");
+            return Err(MeilisearchSchemaError::Sdk(e));
         }
+    };
+
+    let (changes, changed_keys) = settings_changes(&current, &desired)?;
+    if changed_keys.is_empty() {
+        info!(index = %index_settings.name, "index settings already up to date");
+        return Ok((SchemaChangeReport::default(), None));
+    }
+
+    match index.set_settings(&changes).await {
+        Ok(task) => {
+            info!(
+                index = %index_settings.name,
+                task_uid = task.task_uid,
+                changed = ?changed_keys,
+                "applied changed index settings"
+            );
+            // The version must not be recorded for settings that then fail
+            await_task(client, task.clone()).await?;
+            forget_max_total_hits(client, &index_settings.name);
+            if schema.schema_version() > 0 {
+                set_schema_version(client, &index_settings.name, schema.schema_version()).await?;
+            }
+            let report = SchemaChangeReport {
+                index_created: false,
+                settings_changed: changed_keys,
+            };
+            Ok((report, Some(task)))
+        }
+        Err(e) => {
+            error!(index = %index_settings.name, error = %e, "failed to apply index settings");
+            Err(MeilisearchSchemaError::Sdk(e))
+        }
+    }
+}
+
+/// Build a settings object holding only the keys of `desired` whose values
+/// differ from `current`, along with the names of those keys
+fn settings_changes(
+    current: &Settings,
+    desired: &Settings,
+) -> Result<(Settings, Vec<String>), MeilisearchSchemaError> {
+    let to_map = |settings: &Settings| match serde_json::to_value(settings) {
+        Ok(serde_json::Value::Object(map)) => Ok(map),
+        Ok(_) => Err(MeilisearchSchemaError::Conversion(
+            "Settings did not serialize to an object".to_string(),
+        )),
+        Err(e) => Err(MeilisearchSchemaError::Conversion(e.to_string())),
+    };
+    let normalize = |key: &str, value: &serde_json::Value| match value {
+        serde_json::Value::Array(items) if UNORDERED_SETTINGS.contains(&key) => {
+            let mut items: Vec<String> = items.iter().map(|item| item.to_string()).collect();
+            items.sort();
+            serde_json::json!(items)
+        }
+        value => value.clone(),
+    };
+
+    let current = to_map(current)?;
+    let mut changes = serde_json::Map::new();
+    for (key, value) in to_map(desired)? {
+        let unchanged = current
+            .get(&key)
+            .is_some_and(|existing| normalize(&key, existing) == normalize(&key, &value));
+        if !unchanged {
+            changes.insert(key, value);
+        }
+    }
+
+    let changed_keys = changes.keys().cloned().collect();
+    let changes = serde_json::from_value(serde_json::Value::Object(changes))
+        .map_err(|e| MeilisearchSchemaError::Conversion(e.to_string()))?;
+    Ok((changes, changed_keys))
+}
+
+/// Check a user profile schema for misconfiguration before applying it
+///
+/// The index name and primary key must be set, attribute lists must not
+/// contain duplicates, and every attribute must be a field of
+/// `UserProfileDocument` or one of the schema's `custom_fields`.
+pub fn validate_schema(schema: &UserProfileSchema) -> Result<(), MeilisearchSchemaError> {
+    let index_settings = schema
+        .index_settings()
+        .ok_or_else(|| MeilisearchSchemaError::schema("No index settings provided".to_string()))?;
+
+    if index_settings.name.trim().is_empty() {
+        return Err(MeilisearchSchemaError::schema(
+            "Index name must not be empty".to_string(),
+        ));
+    }
+
+    if index_settings.primary_key.trim().is_empty() {
+        return Err(MeilisearchSchemaError::schema(
+            "Primary key must not be empty".to_string(),
+        ));
+    }
+    check_known_field(schema, "primary key", &index_settings.primary_key)?;
+
+    let attribute_lists = [
+        ("searchable", schema.searchable()),
+        ("filterable", schema.filterable()),
+        ("sortable", schema.sortable()),
+    ];
+    for (kind, attributes) in attribute_lists {
+        let attributes = attributes.unwrap_or_default();
+        for (i, attribute) in attributes.iter().enumerate() {
+            if attributes[..i].contains(attribute) {
+                return Err(MeilisearchSchemaError::schema(format!(
+                    "Duplicate {} attribute '{}'",
+                    kind, attribute
+                )));
+            }
+            check_known_field(schema, kind, attribute)?;
+        }
+    }
+
+    if let Some(rules) = schema.ranking() {
+        check_ranking_rules(rules, schema.sortable().unwrap_or_default())?;
+    }
+
+    if let Some(distinct) = schema.distinct_attribute() {
+        check_known_field(schema, "distinct", distinct)?;
+    }
+
+    if let Some(pagination) = schema.pagination() {
+        if pagination.max_total_hits == 0 {
+            return Err(MeilisearchSchemaError::schema(
+                "Pagination max_total_hits must be positive".to_string(),
+            ));
+        }
+    }
+
+    if let Some(faceting) = schema.faceting() {
+        if faceting.max_values_per_facet == 0 {
+            return Err(MeilisearchSchemaError::schema(
+                "Faceting max_values_per_facet must be positive".to_string(),
+            ));
+        }
+    }
+
+    if let Some(typo) = schema.typo_tolerance() {
+        let (one_typo, two_typos) = min_word_sizes_for_typos(typo);
+        if two_typos > u32::from(u8::MAX) {
+            return Err(MeilisearchSchemaError::schema(format!(
+                "Typo tolerance word sizes must be at most {}",
+                u8::MAX
+            )));
+        }
+        if one_typo > two_typos {
+            return Err(MeilisearchSchemaError::schema(format!(
+                "Typo tolerance min_word_size_for_one_typo ({}) must not exceed min_word_size_for_two_typos ({})",
+                one_typo, two_typos
+            )));
+        }
+        for attribute in &typo.disable_on_attributes {
+            check_known_field(schema, "typo tolerance", attribute)?;
+        }
+    }
+
+    for field in &schema.custom_fields {
+        if field.trim().is_empty() {
+            return Err(MeilisearchSchemaError::schema(
+                "Custom field names must not be empty".to_string(),
+            ));
+        }
+    }
+
+    for embedder in schema.embedders() {
+        if embedder.name.trim().is_empty() {
+            return Err(MeilisearchSchemaError::schema(
+                "Embedder name must not be empty".to_string(),
+            ));
+        }
+        if embedder.source.trim().is_empty() {
+            return Err(MeilisearchSchemaError::schema(format!(
+                "Embedder '{}' must have a source",
+                embedder.name
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Ensure an attribute names a field of `UserProfileDocument` or one of the
+/// schema's custom fields
+fn check_known_field(
+    schema: &UserProfileSchema,
+    kind: &str,
+    attribute: &str,
+) -> Result<(), MeilisearchSchemaError> {
+    if USER_PROFILE_FIELDS.contains(&attribute)
+        || schema.custom_fields.iter().any(|field| field == attribute)
+    {
+        Ok(())
+    } else {
+        Err(MeilisearchSchemaError::schema(format!(
+            "Unknown {} attribute '{}'",
+            kind, attribute
+        )))
+    }
+}
+
+/// Check that every ranking rule is a built-in rule or a custom `field:asc` /
+/// `field:desc` rule on a sortable field of the user profile schema
+pub fn validate_ranking_rules<S: AsRef<str>>(rules: &[S]) -> Result<(), MeilisearchSchemaError> {
+    let schema = get_user_profile_schema();
+    check_ranking_rules(rules, schema.sortable().unwrap_or_default())
+}
+
+/// Check ranking rules against the sortable attributes they may refer to
+fn check_ranking_rules<S: AsRef<str>>(
+    rules: &[S],
+    sortable: &[String],
+) -> Result<(), MeilisearchSchemaError> {
+    for (i, rule) in rules.iter().enumerate() {
+        let rule = rule.as_ref();
+        if rules[..i].iter().any(|r| r.as_ref() == rule) {
+            return Err(MeilisearchSchemaError::schema(format!(
+                "Duplicate ranking rule '{}'",
+                rule
+            )));
+        }
+
+        if let Some(field) = rule.parse::<RankingRule>()?.field() {
+            if !sortable.iter().any(|s| s == field) {
+                return Err(MeilisearchSchemaError::schema(format!(
+                    "Ranking rule '{}' refers to '{}', which is not sortable",
+                    rule, field
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Replace the ranking rules of the user profiles index
+///
+/// Only the ranking rules are updated; every other index setting is left as
+/// is, so this can be used to try out alternative rankings without
+/// reapplying the full schema. The recorded schema version is cleared, since
+/// the index no longer matches it. Returns the settings update task.
+pub async fn apply_ranking_rules(
+    client: &Client,
+    rules: &[&str],
+) -> Result<TaskInfo, MeilisearchSchemaError> {
+    validate_ranking_rules(rules)?;
+
+    let index_settings = user_profile_index_settings()?;
+    let index = client.index(&index_settings.name);
+    let settings = Settings::new().with_ranking_rules(rules);
+    match index.set_settings(&settings).await {
+        Ok(task) => {
+            info!(
+                index = %index_settings.name,
+                task_uid = task.task_uid,
+                rules = ?rules,
+                "applied ranking rules"
+            );
+            clear_schema_version(client, &index_settings.name).await?;
+            Ok(task)
+        }
+        Err(e) => {
+            error!(
+                index = %index_settings.name,
+                error = %e,
+                "failed to apply ranking rules"
+            );
+            Err(MeilisearchSchemaError::Sdk(e))
+        }
+    }
+}
+
+/// A single index setting that can be reset to its Meilisearch default
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingKind {
+    Synonyms,
+    StopWords,
+    RankingRules,
+    Filterable,
+    Sortable,
+    Searchable,
+}
+
+/// Reset one setting of the user profiles index to the Meilisearch default
+///
+/// Every other setting is left as is. The recorded schema version is
+/// cleared, since the index no longer matches it; reapply the schema to
+/// restore the configured value. Returns the settings update task.
+pub async fn reset_user_profile_setting(
+    client: &Client,
+    setting: SettingKind,
+) -> Result<TaskInfo, MeilisearchSchemaError> {
+    let index_settings = user_profile_index_settings()?;
+    let index = client.index(&index_settings.name);
+    let result = match setting {
+        SettingKind::Synonyms => index.reset_synonyms().await,
+        SettingKind::StopWords => index.reset_stop_words().await,
+        SettingKind::RankingRules => index.reset_ranking_rules().await,
+        SettingKind::Filterable => index.reset_filterable_attributes().await,
+        SettingKind::Sortable => index.reset_sortable_attributes().await,
+        SettingKind::Searchable => index.reset_searchable_attributes().await,
+    };
+
+    match result {
+        Ok(task) => {
+            info!(
+                index = %index_settings.name,
+                task_uid = task.task_uid,
+                setting = ?setting,
+                "reset index setting"
+            );
+            clear_schema_version(client, &index_settings.name).await?;
+            Ok(task)
+        }
+        Err(e) => {
+            error!(
+                index = %index_settings.name,
+                setting = ?setting,
+                error = %e,
+                "failed to reset index setting"
+            );
+            Err(MeilisearchSchemaError::Sdk(e))
+        }
+    }
+}
+
+/// Preview what `apply_user_profile_schema` would do without changing anything
+///
+/// Validates the schema, checks whether the index would need to be created,
+/// and returns the settings that would be applied. Only read-only requests are
+/// made against the server.
+pub async fn apply_user_profile_schema_dry_run(
+    client: &Client,
+) -> Result<Settings, MeilisearchSchemaError> {
+    let schema = get_user_profile_schema();
+    validate_schema(&schema)?;
+
+    let index_settings = user_profile_index_settings()?;
+    match client.get_raw_index(&index_settings.name).await {
+        Ok(_) => {
+            info!(
+                index = %index_settings.name,
+                "dry run: index already exists and would be kept"
+            );
+        }
+        Err(e) if is_index_not_found(&e) => {
+            info!(
+                index = %index_settings.name,
+                primary_key = %index_settings.primary_key,
+                "dry run: would create index"
+            );
+        }
+        Err(e) => return Err(MeilisearchSchemaError::Sdk(e)),
+    }
+
+    let settings = user_profile_settings();
+    info!(
+        index = %index_settings.name,
+        settings = ?settings,
+        "dry run: would apply settings"
+    );
+
+    Ok(settings)
+}
+
+/// Apply any schema definition to Meilisearch
+///
+/// Creates the index if it doesn't exist yet, then applies every setting the
+/// schema defines, waiting for each task to finish. Only then is
+/// the schema version recorded, in [`SCHEMA_VERSIONS_INDEX`], which is
+/// created on first use. If the server rejects the settings, up front or
+/// while processing them, and the index was created by this call, the index
+/// is deleted again before the error is returned; a pre-existing index is
+/// never deleted.
+pub async fn apply_schema<T: SchemaDefinition>(
+    client: &Client,
+    schema: &T,
+) -> Result<(), MeilisearchSchemaError> {
+    apply_schema_tasks(client, schema).await?;
+    Ok(())
+}
+
+/// [`apply_schema`], returning the settings tasks it waited for
+async fn apply_schema_tasks<T: SchemaDefinition>(
+    client: &Client,
+    schema: &T,
+) -> Result<Vec<TaskInfo>, MeilisearchSchemaError> {
+    // Extract index settings
+    let index_settings = schema
+        .index_settings()
+        .ok_or_else(|| MeilisearchSchemaError::schema("No index settings provided".to_string()))?;
+
+    // Create the index if it doesn't exist
+    let index_name = &index_settings.name;
+    let primary_key = &index_settings.primary_key;
+
+    // Check for the index first so a failed apply only ever removes an index
+    // that this call created
+    let index_existed = match client.get_raw_index(index_name).await {
+        Ok(_) => true,
+        Err(e) if is_index_not_found(&e) => false,
+        Err(e) => return Err(MeilisearchSchemaError::Sdk(e)),
+    };
+
+    // Create index
+    let mut created_index = false;
+    if !index_existed {
+        match client.create_index(index_name, Some(primary_key)).await {
+            Ok(task) => {
+                info!(
+                    index = %index_name,
+                    primary_key = %primary_key,
+                    task_uid = task.task_uid,
+                    "created index"
+                );
+                // If another client creates the index first, this task fails
+                // and the index isn't ours to roll back
+                match await_task(client, task).await {
+                    Ok(()) => created_index = true,
+                    Err(e)
+                        if e.task_error()
+                            .is_some_and(|task| task.code == ErrorCode::IndexAlreadyExists) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+            Err(e) => {
+                // If the error is that the index already exists, that's okay
+                if !is_index_already_exists(&e) {
+                    return Err(MeilisearchSchemaError::Sdk(e));
+                }
+            }
+        }
+    }
+
+    let result = match apply_index_settings(client, index_name, schema).await {
+        Ok(tasks) => await_tasks(client, tasks).await,
+        Err(e) => Err(MeilisearchSchemaError::Sdk(e)),
+    };
+    forget_max_total_hits(client, index_name);
+    let tasks = match result {
+        Ok(tasks) => tasks,
+        Err(e) => {
+            // Don't leave a half-configured empty index behind
+            if created_index {
+                rollback_created_index(client, index_name).await;
+            }
+            return Err(e);
+        }
+    };
+
+    // Recorded only once the settings are live, so a failed apply is retried
+    // by the next migration
+    if schema.schema_version() > 0 {
+        set_schema_version(client, index_name, schema.schema_version()).await?;
+    }
+    Ok(tasks)
+}
+
+/// Wait for each task in turn, stopping at the first that fails
+async fn await_tasks(
+    client: &Client,
+    tasks: Vec<TaskInfo>,
+) -> Result<Vec<TaskInfo>, MeilisearchSchemaError> {
+    for task in &tasks {
+        await_task(client, task.clone()).await?;
+    }
+    Ok(tasks)
+}
+
+/// Submit a schema's settings and embedders to an existing index, returning
+/// the enqueued tasks
+async fn apply_index_settings<T: SchemaDefinition>(
+    client: &Client,
+    index_name: &str,
+    schema: &T,
+) -> Result<Vec<TaskInfo>, SdkError> {
+    // Configure settings
+    let settings = build_settings(schema);
+
+    // Apply settings
+    let index = client.index(index_name);
+    let mut tasks = Vec::with_capacity(2);
+    match index.set_settings(&settings).await {
+        Ok(task) => {
+            info!(
+                index = %index_name,
+                task_uid = task.task_uid,
+                "applied index settings"
+            );
+            tasks.push(task);
+        }
+        Err(e) => {
+            error!(index = %index_name, error = %e, "failed to apply index settings");
+            return Err(e);
+        }
+    }
+
+    // Embedders aren't part of the SDK's settings object, so they're applied
+    // with a separate request
+    if !schema.embedders().is_empty() {
+        match set_embedders(client, index_name, schema.embedders()).await {
+            Ok(task) => {
+                info!(
+                    index = %index_name,
+                    task_uid = task.task_uid,
+                    "applied embedder settings"
+                );
+                tasks.push(task);
+            }
+            Err(e) => {
+                error!(index = %index_name, error = %e, "failed to apply embedder settings");
+                return Err(e);
+            }
+        }
+    }
+
+    Ok(tasks)
+}
+
+/// Delete an index created by a schema application that then failed
+///
+/// Failure to roll back is logged rather than returned so the original error
+/// reaches the caller.
+async fn rollback_created_index(client: &Client, index_name: &str) {
+    match client.delete_index(index_name).await {
+        Ok(task) => {
+            info!(
+                index = %index_name,
+                task_uid = task.task_uid,
+                "rolled back index created by failed schema application"
+            );
+        }
+        Err(e) => {
+            error!(
+                index = %index_name,
+                error = %e,
+                "failed to roll back index created by failed schema application"
+            );
+        }
+    }
+}
+
+/// Record of the schema version applied to an index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SchemaVersionDocument {
+    index: String,
+    version: u32,
+}
+
+/// Get the schema version last applied to the user profiles index
+///
+/// Returns 0 if no version has been recorded, i.e. the index predates
+/// schema versioning or doesn't exist yet.
+pub async fn current_schema_version(client: &Client) -> Result<u32, MeilisearchSchemaError> {
+    let index_settings = user_profile_index_settings()?;
+    match client
+        .index(SCHEMA_VERSIONS_INDEX)
+        .get_document::<SchemaVersionDocument>(&index_settings.name)
+        .await
+    {
+        Ok(doc) => Ok(doc.version),
+        Err(e) if is_index_not_found(&e) || is_document_not_found(&e) => Ok(0),
+        Err(e) => {
+            error!(index = %index_settings.name, error = %e, "failed to get schema version");
+            Err(MeilisearchSchemaError::Sdk(e))
+        }
+    }
+}
+
+/// Record the schema version applied to an index and wait for it to be stored
+async fn set_schema_version(
+    client: &Client,
+    index_name: &str,
+    version: u32,
+) -> Result<(), MeilisearchSchemaError> {
+    let doc = SchemaVersionDocument {
+        index: index_name.to_string(),
+        version,
+    };
+    let task = client
+        .index(SCHEMA_VERSIONS_INDEX)
+        .add_or_replace(&[doc], Some("index"))
+        .await
+        .map_err(|e| {
+            error!(index = %index_name, error = %e, "failed to store schema version");
+            MeilisearchSchemaError::Sdk(e)
+        })?;
+    await_task(client, task).await?;
+
+    info!(index = %index_name, version, "stored schema version");
+    Ok(())
+}
+
+/// Forget the schema version recorded for an index, so that
+/// [`current_schema_version`] reports 0 again
+async fn clear_schema_version(
+    client: &Client,
+    index_name: &str,
+) -> Result<(), MeilisearchSchemaError> {
+    let task = match client
+        .index(SCHEMA_VERSIONS_INDEX)
+        .delete_document(index_name)
+        .await
+    {
+        Ok(task) => task,
+        Err(e) => {
+            error!(index = %index_name, error = %e, "failed to clear schema version");
+            return Err(MeilisearchSchemaError::Sdk(e));
+        }
+    };
+
+    match await_task(client, task).await {
+        // Nothing was ever recorded if the versions index doesn't exist
+        Err(e)
+            if e.task_error()
+                .is_some_and(|task| task.code == ErrorCode::IndexNotFound) =>
+        {
+            Ok(())
+        }
+        Err(e) => Err(e),
+        Ok(()) => {
+            info!(index = %index_name, "cleared schema version");
+            Ok(())
+        }
+    }
+}
+
+/// Migrate the user profiles index from schema version `from` to `to`
+///
+/// The stored version must match `from`. Each supported version pair applies
+/// whatever changes that upgrade needs, and the stored version is bumped to
+/// `to` once they have succeeded. Unsupported pairs are rejected without
+/// changing anything.
+pub async fn migrate_schema(
+    client: &Client,
+    from: u32,
+    to: u32,
+) -> Result<(), MeilisearchSchemaError> {
+    let current = current_schema_version(client).await?;
+    if current != from {
+        return Err(MeilisearchSchemaError::schema(format!(
+            "Cannot migrate from schema version {}: index is at version {}",
+            from, current
+        )));
+    }
+
+    match (from, to) {
+        (from, to) if from == to => {
+            info!(version = from, "schema already at target version");
+            return Ok(());
+        }
+        // Every version so far only changes index settings, so any earlier
+        // version is upgraded by reapplying the current schema, which also
+        // records the new version. Fields added along the way read as their
+        // defaults in existing documents
+        (from, USER_PROFILE_SCHEMA_VERSION) if from < USER_PROFILE_SCHEMA_VERSION => {
+            apply_user_profile_schema(client).await?
+        }
+        _ => {
+            return Err(MeilisearchSchemaError::schema(format!(
+                "No migration from schema version {} to {}",
+                from, to
+            )));
+        }
+    }
+
+    info!(from, to, "migrated user profiles schema");
+    Ok(())
+}
+
+/// Update the embedders of an index
+async fn set_embedders(
+    client: &Client,
+    index_name: &str,
+    embedders: &[EmbedderSettings],
+) -> Result<TaskInfo, SdkError> {
+    let body: serde_json::Map<String, serde_json::Value> = embedders
+        .iter()
+        .map(|embedder| {
+            let mut config = serde_json::json!({ "source": embedder.source });
+            // Some sources infer dimensions from the model, so 0 means unset
+            if embedder.dimensions > 0 {
+                config["dimensions"] = serde_json::Value::from(embedder.dimensions);
+            }
+            if let Some(template) = &embedder.document_template {
+                config["documentTemplate"] = serde_json::Value::from(template.as_str());
+            }
+            (embedder.name.clone(), config)
+        })
+        .collect();
+
+    ReqwestClient::new(client.get_api_key())?
+        .request::<(), _, TaskInfo>(
+            &format!(
+                "{}/indexes/{}/settings/embedders",
+                client.get_host(),
+                index_name
+            ),
+            Method::Patch { query: (), body },
+            202,
+        )
+        .await
+}
+
+/// Build the Meilisearch settings described by a schema
+fn build_settings<T: SchemaDefinition>(schema: &T) -> Settings {
+    let mut settings = Settings::new();
+
+    // Searchable attributes
+    if let Some(searchable) = schema.searchable() {
+        settings = settings.with_searchable_attributes(searchable);
+    }
+
+    // Ranking rules
+    if let Some(rules) = schema.ranking() {
+        settings = settings.with_ranking_rules(rules);
+    }
+
+    // Distinct attribute
+    if let Some(distinct) = schema.distinct_attribute() {
+        settings = settings.with_distinct_attribute(Some(distinct));
+    }
+
+    // Filterable attributes
+    if let Some(filterable) = schema.filterable() {
+        settings = settings.with_filterable_attributes(filterable);
+    }
+
+    // Sortable attributes
+    if let Some(sortable) = schema.sortable() {
+        settings = settings.with_sortable_attributes(sortable);
+    }
+
+    // Pagination
+    if let Some(pagination) = schema.pagination() {
+        settings = settings.with_pagination(PaginationSetting {
+            max_total_hits: pagination.max_total_hits as usize,
+        });
+    }
+
+    // Faceting
+    if let Some(faceting) = schema.faceting() {
+        settings = settings.with_faceting(&FacetingSettings {
+            max_values_per_facet: faceting.max_values_per_facet as usize,
+        });
+    }
+
+    // Typo tolerance, spelled out in full: Meilisearch resets any field sent
+    // as null, and a complete object compares cleanly against the live one
+    if let Some(typo) = schema.typo_tolerance() {
+        let (one_typo, two_typos) = min_word_sizes_for_typos(typo);
+        settings = settings.with_typo_tolerance(TypoToleranceSettings {
+            enabled: Some(typo.enabled.unwrap_or(true)),
+            disable_on_attributes: Some(typo.disable_on_attributes.clone()),
+            disable_on_words: Some(Vec::new()),
+            min_word_size_for_typos: Some(MinWordSizeForTypos {
+                one_typo: u8::try_from(one_typo).ok(),
+                two_typos: u8::try_from(two_typos).ok(),
+            }),
+        });
+    }
+
+    settings
+}
+
+/// Word sizes from which one and two typos are tolerated, with 0 replaced by
+/// the Meilisearch default
+fn min_word_sizes_for_typos(typo: &crate::proto::meilisearch::TypoToleranceSettings) -> (u32, u32) {
+    let one_typo = match typo.min_word_size_for_one_typo {
+        0 => DEFAULT_MIN_WORD_SIZE_FOR_ONE_TYPO,
+        size => size,
+    };
+    let two_typos = match typo.min_word_size_for_two_typos {
+        0 => DEFAULT_MIN_WORD_SIZE_FOR_TWO_TYPOS,
+        size => size,
+    };
+    (one_typo, two_typos)
+}
+
+/// The Meilisearch settings `apply_user_profile_schema` applies
+///
+/// Embedders aren't part of the SDK's `Settings` and are left out. The result
+/// can be adjusted and then applied with [`apply_user_profile_settings`].
+pub fn user_profile_settings() -> Settings {
+    build_settings(&get_user_profile_schema())
+}
+
+/// Apply a settings object to the user profiles index
+///
+/// Only the settings that are set on `settings` are changed. The index must
+/// already exist. The recorded schema version is cleared, since arbitrary
+/// settings can leave the index out of line with it; reapply the schema to
+/// record it again. Returns the settings update task.
+pub async fn apply_user_profile_settings(
+    client: &Client,
+    settings: &Settings,
+) -> Result<TaskInfo, MeilisearchSchemaError> {
+    let index_settings = user_profile_index_settings()?;
+    let index = client.index(&index_settings.name);
+    match index.set_settings(settings).await {
+        Ok(task) => {
+            info!(
+                index = %index_settings.name,
+                task_uid = task.task_uid,
+                "applied index settings"
+            );
+            forget_max_total_hits(client, &index_settings.name);
+            clear_schema_version(client, &index_settings.name).await?;
+            Ok(task)
+        }
+        Err(e) => {
+            error!(
+                index = %index_settings.name,
+                error = %e,
+                "failed to apply index settings"
+            );
+            Err(MeilisearchSchemaError::Sdk(e))
+        }
+    }
+}
+
+/// Snapshot the live user profile index settings as JSON
+///
+/// The snapshot reflects the index as it is, including any changes made
+/// outside this crate's schema, so [`restore_settings`] can reproduce it.
+pub async fn dump_settings(client: &Client) -> Result<String, MeilisearchSchemaError> {
+    let index_settings = user_profile_index_settings()?;
+    let index = client.index(&index_settings.name);
+    let settings = index.get_settings().await.map_err(|e| {
+        error!(index = %index_settings.name, error = %e, "failed to get index settings");
+        MeilisearchSchemaError::Sdk(e)
+    })?;
+
+    serde_json::to_string_pretty(&settings)
+        .map_err(|e| MeilisearchSchemaError::Conversion(e.to_string()))
+}
+
+/// Apply settings previously captured by [`dump_settings`]
+///
+/// A dump holds only the index settings, not the schema version they were
+/// taken at, so the recorded version is cleared rather than guessed. Reapply
+/// the schema afterwards if the index should track it again.
+pub async fn restore_settings(
+    client: &Client,
+    json: &str,
+) -> Result<TaskInfo, MeilisearchSchemaError> {
+    let settings: Settings = serde_json::from_str(json)
+        .map_err(|e| MeilisearchSchemaError::Conversion(e.to_string()))?;
+    apply_user_profile_settings(client, &settings).await
+}
+
+/// Get the predefined user profile schema
+pub fn get_user_profile_schema() -> UserProfileSchema {
+    UserProfileSchema::builder().build()
+}
+
+impl UserProfileSchema {
+    /// Start from the predefined user profile schema and adjust it
+    pub fn builder() -> UserProfileSchemaBuilder {
+        UserProfileSchemaBuilder::new()
+    }
+}
+
+/// Builder for variations of the predefined user profile schema
+///
+/// Starts from the same settings as [`get_user_profile_schema`]. Attributes
+/// that aren't fields of [`UserProfile`] are recorded as the schema's
+/// `custom_fields`, so that [`validate_schema`] accepts them; documents keep
+/// such fields in `extra`. Apply the result with
+/// [`apply_user_profile_schema_with`]. The other helpers always work against
+/// the predefined schema.
+#[derive(Debug, Clone)]
+pub struct UserProfileSchemaBuilder {
+    schema: UserProfileSchema,
+}
+
+impl Default for UserProfileSchemaBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UserProfileSchemaBuilder {
+    /// Start from the predefined schema
+    pub fn new() -> Self {
+        Self {
+            schema: default_user_profile_schema(),
+        }
+    }
+
+    /// Append a searchable attribute, as the least important one
+    pub fn add_searchable(mut self, attribute: impl Into<String>) -> Self {
+        let attribute = attribute.into();
+        self.register_field(&attribute);
+        self.schema
+            .searchable
+            .get_or_insert_with(Default::default)
+            .attributes
+            .push(attribute);
+        self
+    }
+
+    /// Replace the searchable attributes, in order of importance
+    pub fn set_searchable<I, S>(mut self, attributes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let attributes: Vec<String> = attributes.into_iter().map(Into::into).collect();
+        for attribute in &attributes {
+            self.register_field(attribute);
+        }
+        self.schema.searchable = Some(SearchableAttributes { attributes });
+        self
+    }
+
+    /// Add a filterable attribute
+    pub fn add_filterable(mut self, attribute: impl Into<String>) -> Self {
+        let attribute = attribute.into();
+        self.register_field(&attribute);
+        self.schema
+            .filterable
+            .get_or_insert_with(Default::default)
+            .attributes
+            .push(attribute);
+        self
+    }
+
+    /// Replace the filterable attributes
+    pub fn set_filterable<I, S>(mut self, attributes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let attributes: Vec<String> = attributes.into_iter().map(Into::into).collect();
+        for attribute in &attributes {
+            self.register_field(attribute);
+        }
+        self.schema.filterable = Some(FilterableAttributes { attributes });
+        self
+    }
+
+    /// Add a sortable attribute
+    pub fn add_sortable(mut self, attribute: impl Into<String>) -> Self {
+        let attribute = attribute.into();
+        self.register_field(&attribute);
+        self.schema
+            .sortable
+            .get_or_insert_with(Default::default)
+            .attributes
+            .push(attribute);
+        self
+    }
+
+    /// Replace the sortable attributes
+    pub fn set_sortable<I, S>(mut self, attributes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let attributes: Vec<String> = attributes.into_iter().map(Into::into).collect();
+        for attribute in &attributes {
+            self.register_field(attribute);
+        }
+        self.schema.sortable = Some(SortableAttributes { attributes });
+        self
+    }
+
+    /// Replace the ranking rules, in order of application
+    pub fn set_ranking(mut self, rules: impl IntoIterator<Item = RankingRule>) -> Self {
+        self.schema.ranking = Some(RankingRules {
+            rules: rules.into_iter().map(|rule| rule.to_string()).collect(),
+        });
+        self
+    }
+
+    /// Replace the attribute search results are deduplicated on; an empty
+    /// string leaves the index's setting untouched
+    pub fn set_distinct_attribute(mut self, attribute: impl Into<String>) -> Self {
+        let attribute = attribute.into();
+        if !attribute.is_empty() {
+            self.register_field(&attribute);
+        }
+        self.schema.distinct_attribute = attribute;
+        self
+    }
+
+    /// Add an embedder for semantic search
+    pub fn add_embedder(mut self, embedder: EmbedderSettings) -> Self {
+        self.schema.embedders.push(embedder);
+        self
+    }
+
+    /// Record an attribute as a custom field unless the document defines it
+    fn register_field(&mut self, attribute: &str) {
+        let known = USER_PROFILE_FIELDS.contains(&attribute)
+            || self.schema.custom_fields.iter().any(|f| f == attribute);
+        if !known {
+            self.schema.custom_fields.push(attribute.to_string());
+        }
+    }
+
+    /// Finish the schema
+    pub fn build(self) -> UserProfileSchema {
+        self.schema
+    }
+}
+
+/// The predefined user profile schema that [`UserProfileSchemaBuilder`]
+/// starts from
+fn default_user_profile_schema() -> UserProfileSchema {
+    let mut schema = UserProfileSchema::default();
+
+    // Index settings
+    let index = crate::proto::meilisearch::user_profile_schema::IndexSettings {
+        name: "user_profiles".to_string(),
+        primary_key: "id".to_string(),
+    };
+
+    // Searchable attributes
+    let searchable = SearchableAttributes {
+        attributes: vec![
+            "username".to_string(),
+            "display_name".to_string(),
+            "bio".to_string(),
+            "location".to_string(),
+            "twitter".to_string(),
+            "github".to_string(),
+            "fid".to_string(),
+        ],
+    };
+
+    // Ranking rules
+    let ranking = RankingRules {
+        rules: [
+            RankingRule::Words,
+            RankingRule::Typo,
+            RankingRule::Proximity,
+            RankingRule::Attribute,
+            RankingRule::Sort,
+            RankingRule::Exactness,
+        ]
+        .iter()
+        .map(RankingRule::to_string)
+        .collect(),
+    };
+
+    // Filterable attributes
+    let filterable = FilterableAttributes {
+        attributes: vec![
+            "id".to_string(),
+            "fid".to_string(),
+            "username".to_string(),
+            "username_normalized".to_string(),
+            "_geo".to_string(),
+            "verified".to_string(),
+            "created_at".to_string(),
+            "account_type".to_string(),
+            "updated_at".to_string(),
+            "wallet_addresses_normalized".to_string(),
+        ],
+    };
+
+    // Sortable attributes
+    let sortable = SortableAttributes {
+        attributes: vec![
+            "fid".to_string(),
+            "updated_at".to_string(),
+            "_geo".to_string(),
+            "follower_count".to_string(),
+            "following_count".to_string(),
+            "created_at".to_string(),
+            "verified".to_string(),
+        ],
+    };
+
+    // Pagination, raised from Meilisearch's default of 1000 so deep pages
+    // aren't silently truncated
+    let pagination = crate::proto::meilisearch::PaginationSettings {
+        max_total_hits: 10_000,
+    };
+
+    // Faceting, raised from Meilisearch's default of 100 values per facet
+    let faceting = crate::proto::meilisearch::FacetingSettings {
+        max_values_per_facet: 1000,
+    };
+
+    // Typo tolerance, kept off usernames so handle lookups only match exactly
+    let typo_tolerance = crate::proto::meilisearch::TypoToleranceSettings {
+        enabled: Some(true),
+        min_word_size_for_one_typo: DEFAULT_MIN_WORD_SIZE_FOR_ONE_TYPO,
+        min_word_size_for_two_typos: DEFAULT_MIN_WORD_SIZE_FOR_TWO_TYPOS,
+        disable_on_attributes: vec!["username".to_string()],
+    };
+
+    // Set the fields
+    schema.index = Some(index);
+    schema.searchable = Some(searchable);
+    schema.ranking = Some(ranking);
+    // Collapses profiles sharing a username; searches can override this with
+    // `SearchOptions::distinct`
+    schema.distinct_attribute = "username".to_string();
+    schema.filterable = Some(filterable);
+    schema.sortable = Some(sortable);
+    schema.pagination = Some(pagination);
+    schema.faceting = Some(faceting);
+    schema.typo_tolerance = Some(typo_tolerance);
+    schema.schema_version = USER_PROFILE_SCHEMA_VERSION;
+
+    schema
+}
+
+/// A user profile document field and what the schema allows it to be used for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldInfo {
+    pub name: String,
+    pub searchable: bool,
+    pub filterable: bool,
+    pub sortable: bool,
+}
+
+/// List every user profile document field with its search capabilities
+///
+/// Capabilities are read from [`get_user_profile_schema`], so this always
+/// matches the settings that schema applies.
+pub fn user_profile_fields() -> Vec<FieldInfo> {
+    let schema = get_user_profile_schema();
+    let has = |attributes: Option<&[String]>, name: &str| {
+        attributes.is_some_and(|attributes| attributes.iter().any(|a| a == name))
+    };
+
+    USER_PROFILE_FIELDS
+        .iter()
+        .map(|&name| FieldInfo {
+            name: name.to_string(),
+            // Meilisearch searches every field unless told otherwise
+            searchable: schema.searchable().is_none() || has(schema.searchable(), name),
+            filterable: has(schema.filterable(), name),
+            sortable: has(schema.sortable(), name),
+        })
+        .collect()
+}
+
+/// Wait for a task to finish and fail if it didn't succeed
+///
+/// Polls the task for up to [`DEFAULT_TASK_TIMEOUT`]. A failed task becomes a
+/// [`MeilisearchSchemaError::Schema`] whose `task` carries the server's error
+/// details.
+pub async fn await_task(client: &Client, task: TaskInfo) -> Result<(), MeilisearchSchemaError> {
+    await_task_with_timeout(client, task, DEFAULT_TASK_TIMEOUT).await
+}
+
+/// Wait for a task to finish within `timeout` and fail if it didn't succeed
+///
+/// A failed task becomes a `Schema` error, as with [`await_task`]; running out
+/// of time is an `Sdk` timeout error.
+pub async fn await_task_with_timeout(
+    client: &Client,
+    task: TaskInfo,
+    timeout: Duration,
+) -> Result<(), MeilisearchSchemaError> {
+    wait_for_succeeded_task(client, task, timeout)
+        .await
+        .map(|_| ())
+}
+
+/// Wait for a task to finish, returning it only if it succeeded
+///
+/// A failed task is reported through the existing `Schema` variant, with the
+/// server's error in its `task` field, so callers matching on `Schema` keep
+/// seeing every failure this helper reports.
+async fn wait_for_succeeded_task(
+    client: &Client,
+    task: TaskInfo,
+    timeout: Duration,
+) -> Result<Task, MeilisearchSchemaError> {
+    let task_uid = task.task_uid;
+    let task = task
+        .wait_for_completion(client, None, Some(timeout))
+        .await
+        .map_err(|e| {
+            error!(task_uid, error = %e, "failed waiting for task");
+            MeilisearchSchemaError::Sdk(e)
+        })?;
+
+    match task {
+        Task::Failed { content } => {
+            error!(task_uid, error = %content.error, "task failed");
+            Err(MeilisearchSchemaError::failed_task(task_uid, content.error))
+        }
+        task => Ok(task),
+    }
+}
+
+/// Index name and primary key from the user profile schema
+fn user_profile_index_settings() -> Result<IndexSettings, MeilisearchSchemaError> {
+    get_user_profile_schema()
+        .index
+        .ok_or_else(|| MeilisearchSchemaError::schema("No index settings provided".to_string()))
+}
+
+/// Create a batch of user profiles in Meilisearch
+pub async fn batch_create_user_profiles(
+    client: &Client,
+    profiles: &[UserProfile],
+) -> Result<(), MeilisearchSchemaError> {
+    batch_create_user_profiles_with(client, profiles, &BatchOptions::default()).await
+}
+
+/// Optional parameters for [`batch_create_user_profiles_with`]
+#[derive(Debug, Clone, Default)]
+pub struct BatchOptions {
+    /// Wait for the batch task and then for the index to finish indexing, so
+    /// searches made afterwards, including sorted ones, see the new profiles
+    pub wait_indexed: bool,
+}
+
+/// [`batch_create_user_profiles`] with options
+///
+/// With `wait_indexed`, waits up to [`DEFAULT_TASK_TIMEOUT`] each for the
+/// task and for indexing to settle.
+pub async fn batch_create_user_profiles_with(
+    client: &Client,
+    profiles: &[UserProfile],
+    options: &BatchOptions,
+) -> Result<(), MeilisearchSchemaError> {
+    let documents = profiles_to_documents(profiles);
+    let task = add_user_profile_documents(client, &documents).await?;
+
+    if options.wait_indexed {
+        await_task(client, task).await?;
+        wait_until_indexed(client, DEFAULT_TASK_TIMEOUT).await?;
+    }
+    Ok(())
+}
+
+/// [`batch_create_user_profiles`], giving up after `timeout`
+pub async fn batch_create_user_profiles_with_timeout(
+    client: &Client,
+    profiles: &[UserProfile],
+    timeout: Duration,
+) -> Result<(), MeilisearchSchemaError> {
+    with_timeout(timeout, batch_create_user_profiles(client, profiles)).await
+}
+
+/// [`batch_create_user_profiles`], first checking that the index uses the
+/// schema's primary key
+///
+/// An index created elsewhere may have a different primary key, which would
+/// make every profile fail to index. A missing index, or one without a
+/// primary key yet, passes the check, as adding the profiles sets it.
+pub async fn batch_create_user_profiles_checked(
+    client: &Client,
+    profiles: &[UserProfile],
+) -> Result<(), MeilisearchSchemaError> {
+    let index_settings = user_profile_index_settings()?;
+    match get_index_primary_key(client).await {
+        Ok(Some(primary_key)) if primary_key != index_settings.primary_key => {
+            return Err(MeilisearchSchemaError::schema(format!(
+                "Index '{}' has primary key '{}', expected '{}'",
+                index_settings.name, primary_key, index_settings.primary_key
+            )));
+        }
+        Ok(_) => {}
+        Err(MeilisearchSchemaError::Sdk(e)) if is_index_not_found(&e) => {}
+        Err(e) => return Err(e),
+    }
+
+    batch_create_user_profiles(client, profiles).await
+}
+
+/// How to resolve several profiles sharing an `id` within one batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateStrategy {
+    /// Keep the first occurrence
+    KeepFirst,
+    /// Keep the last occurrence
+    #[default]
+    KeepLast,
+    /// Keep the profile with the greatest `updated_at`, preferring the later
+    /// occurrence on a tie
+    KeepNewest,
+}
+
+/// Collapse profiles with duplicate ids into one per id
+///
+/// Profiles keep the position of the first occurrence of their id, so the
+/// output order doesn't depend on which duplicate wins.
+pub fn dedup_user_profiles(
+    profiles: Vec<UserProfile>,
+    strategy: DuplicateStrategy,
+) -> Vec<UserProfile> {
+    let total = profiles.len();
+    let mut positions: HashMap<String, usize> = HashMap::with_capacity(total);
+    let mut deduped: Vec<UserProfile> = Vec::with_capacity(total);
+
+    for profile in profiles {
+        let Some(&i) = positions.get(&profile.id) else {
+            positions.insert(profile.id.clone(), deduped.len());
+            deduped.push(profile);
+            continue;
+        };
+
+        let replace = match strategy {
+            DuplicateStrategy::KeepFirst => false,
+            DuplicateStrategy::KeepLast => true,
+            DuplicateStrategy::KeepNewest => profile.updated_at >= deduped[i].updated_at,
+        };
+        if replace {
+            deduped[i] = profile;
+        }
+    }
+
+    let merged = total - deduped.len();
+    if merged > 0 {
+        info!(merged, strategy = ?strategy, "collapsed duplicate profile ids");
+    }
+    deduped
+}
+
+/// Create a batch of user profiles after collapsing duplicate ids
+///
+/// Meilisearch resolves duplicates within a batch by submission order, so
+/// this makes the outcome explicit and independent of upstream ordering.
+pub async fn batch_create_user_profiles_deduped(
+    client: &Client,
+    profiles: &[UserProfile],
+    strategy: DuplicateStrategy,
+) -> Result<(), MeilisearchSchemaError> {
+    let profiles = dedup_user_profiles(profiles.to_vec(), strategy);
+    batch_create_user_profiles(client, &profiles).await
+}
+
+/// Create user profiles in chunks, keeping up to `concurrency` submissions
+/// in flight
+///
+/// Returns the enqueued tasks ordered by task uid, without waiting for them
+/// to be processed. A `chunk_size` or `concurrency` of 0 is treated as 1.
+/// Stops at the first failed submission; chunks already submitted stay
+/// enqueued.
+pub async fn batch_create_user_profiles_parallel(
+    client: &Client,
+    profiles: &[UserProfile],
+    chunk_size: usize,
+    concurrency: usize,
+) -> Result<Vec<TaskInfo>, MeilisearchSchemaError> {
+    batch_create_user_profiles_rate_limited(client, profiles, chunk_size, concurrency, None).await
+}
+
+/// [`batch_create_user_profiles_parallel`], starting at most `rate_limit`
+/// submissions per second
+///
+/// Each chunk is one task, so this caps the rate of enqueued tasks on a shared
+/// instance. Submissions are spaced evenly by an interval timer, with a
+/// submission that falls behind delaying the ones after it rather than
+/// bursting to catch up. The limit is best-effort: it only paces this call,
+/// not other callers or processes, and measures when requests start rather
+/// than when the server receives them. `None` or 0 submits without pacing.
+pub async fn batch_create_user_profiles_rate_limited(
+    client: &Client,
+    profiles: &[UserProfile],
+    chunk_size: usize,
+    concurrency: usize,
+    rate_limit: Option<u32>,
+) -> Result<Vec<TaskInfo>, MeilisearchSchemaError> {
+    // Shared by every submission; the lock is fair, so ticks go out in chunk
+    // order
+    let pacer = rate_limit.filter(|&rate| rate > 0).map(|rate| {
+        let mut interval = tokio::time::interval(Duration::from_secs_f64(1.0 / f64::from(rate)));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        tokio::sync::Mutex::new(interval)
+    });
+    let pacer = &pacer;
+
+    let submissions = profiles.chunks(chunk_size.max(1)).map(|chunk| async move {
+        if let Some(pacer) = pacer {
+            pacer.lock().await.tick().await;
+        }
+        let documents = profiles_to_documents(chunk);
+        add_user_profile_documents(client, &documents).await
+    });
+
+    let mut tasks: Vec<TaskInfo> = stream::iter(submissions)
+        .buffer_unordered(concurrency.max(1))
+        .try_collect()
+        .await?;
+    tasks.sort_by_key(|task| task.task_uid);
+
+    info!(
+        count = profiles.len(),
+        tasks = tasks.len(),
+        "submitted user profiles in parallel"
+    );
+    Ok(tasks)
+}
+
+/// Outcome of a batch submission with per-document failures
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BatchReport {
+    /// Number of documents that were indexed
+    pub succeeded: usize,
+    /// `(id, error)` for each document that was rejected
+    pub failed: Vec<(String, String)>,
+}
+
+/// Create a batch of user profiles and report which documents failed
+///
+/// Meilisearch processes a batch as a single task that either succeeds or
+/// fails as a whole, so if the batch task fails each document is resubmitted
+/// on its own to isolate the bad records. Every task is awaited, making this
+/// slower than [`batch_create_user_profiles`] but letting callers quarantine
+/// failures instead of losing the whole batch.
+pub async fn batch_create_user_profiles_report(
+    client: &Client,
+    profiles: &[UserProfile],
+) -> Result<BatchReport, MeilisearchSchemaError> {
+    let documents = profiles_to_documents(profiles);
+    let mut report = BatchReport::default();
+
+    // Documents the write path rejects would fail the batch without saying
+    // which one was at fault, so set them aside up front
+    let mut writable = Vec::with_capacity(documents.len());
+    for doc in documents {
+        match check_document_fid(&doc)
+            .and_then(|()| check_document_size(&doc, DEFAULT_MAX_DOCUMENT_BYTES))
+        {
+            Ok(()) => writable.push(doc),
+            Err(e) => report.failed.push((doc.id, e.to_string())),
+        }
+    }
+    let documents = writable;
+
+    if documents.is_empty() {
+        return Ok(report);
+    }
+
+    let task = add_user_profile_documents(client, &documents).await?;
+    match await_task(client, task).await {
+        Ok(()) => {
+            report.succeeded = documents.len();
+            return Ok(report);
+        }
+        Err(e) if e.task_error().is_some() => {
+            info!(
+                count = documents.len(),
+                error = %e,
+                "batch task failed, retrying documents individually"
+            );
+        }
+        Err(e) => return Err(e),
+    }
+
+    for doc in &documents {
+        let task = add_user_profile_documents(client, std::slice::from_ref(doc)).await?;
+        match await_task(client, task).await {
+            Ok(()) => report.succeeded += 1,
+            Err(e) if e.task_error().is_some() => {
+                report.failed.push((doc.id.clone(), e.to_string()));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    info!(
+        succeeded = report.succeeded,
+        failed = report.failed.len(),
+        "finished reporting batch of user profiles"
+    );
+    Ok(report)
+}
+
+/// Add or update raw user profile documents in Meilisearch
+///
+/// Derived fields are recomputed with [`UserProfileDocument::normalized`]
+/// first. Returns the enqueued task without waiting for it to be processed.
+/// Fails without submitting anything if a document has fid 0 or is larger
+/// than [`DEFAULT_MAX_DOCUMENT_BYTES`].
+pub async fn add_user_profile_documents(
+    client: &Client,
+    documents: &[UserProfileDocument],
+) -> Result<TaskInfo, MeilisearchSchemaError> {
+    add_user_profile_documents_with_limit(client, documents, DEFAULT_MAX_DOCUMENT_BYTES).await
+}
+
+/// Add or update raw user profile documents, rejecting any document with fid 0
+/// or whose serialized size exceeds `max_document_bytes`
+pub async fn add_user_profile_documents_with_limit(
+    client: &Client,
+    documents: &[UserProfileDocument],
+    max_document_bytes: usize,
+) -> Result<TaskInfo, MeilisearchSchemaError> {
+    let documents: Vec<UserProfileDocument> = documents
+        .iter()
+        .cloned()
+        .map(UserProfileDocument::normalized)
+        .collect();
+    documents.iter().try_for_each(check_document_fid)?;
+    check_document_sizes(&documents, max_document_bytes)?;
+
+    // Add documents to index, keyed on the schema's primary key
+    let index_settings = user_profile_index_settings()?;
+    let index = client.index(&index_settings.name);
+    match index
+        .add_or_update(&documents, Some(&index_settings.primary_key))
+        .await
+    {
+        Ok(task) => {
+            info!(
+                index = %index_settings.name,
+                count = documents.len(),
+                task_uid = task.task_uid,
+                "added user profiles"
+            );
+            Ok(task)
+        }
+        Err(e) => {
+            error!(
+                index = %index_settings.name,
+                error = %e,
+                "failed to add user profiles"
+            );
+            Err(MeilisearchSchemaError::Sdk(e))
+        }
+    }
+}
+
+/// Check that a document has a valid fid
+///
+/// Farcaster never assigns fid 0, and it's also what a document without a
+/// `fid` reads as, so such a document is an `InvalidValue` error rather than a
+/// profile to index.
+pub fn check_document_fid(doc: &UserProfileDocument) -> Result<(), MeilisearchSchemaError> {
+    match Fid::new(doc.fid) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            error!(id = %doc.id, "document has an invalid fid");
+            Err(e.into())
+        }
+    }
+}
+
+/// Check that every document serializes to at most `max_bytes` bytes
+///
+/// Returns a `Conversion` error naming the first oversized document, so one
+/// bad record is reported by id rather than the server rejecting the whole
+/// batch with an opaque payload error.
+pub fn check_document_sizes(
+    documents: &[UserProfileDocument],
+    max_bytes: usize,
+) -> Result<(), MeilisearchSchemaError> {
+    documents
+        .iter()
+        .try_for_each(|doc| check_document_size(doc, max_bytes))
+}
+
+/// Check that a single document serializes to at most `max_bytes` bytes
+///
+/// The document is measured as given, so normalize it first to match what
+/// [`add_user_profile_documents`] would submit.
+pub fn check_document_size(
+    doc: &UserProfileDocument,
+    max_bytes: usize,
+) -> Result<(), MeilisearchSchemaError> {
+    let size = serde_json::to_vec(doc)
+        .map_err(|e| {
+            MeilisearchSchemaError::Conversion(format!(
+                "Failed to serialize document '{}': {}",
+                doc.id, e
+            ))
+        })?
+        .len();
+
+    if size > max_bytes {
+        error!(id = %doc.id, size, max_bytes, "document exceeds size limit");
+        return Err(MeilisearchSchemaError::Conversion(format!(
+            "Document '{}' is {} bytes, exceeding the limit of {} bytes",
+            doc.id, size, max_bytes
+        )));
+    }
+    Ok(())
+}
+
+/// Delete every document from the user profiles index
+///
+/// The index itself and its settings are kept, so it can be repopulated
+/// without reapplying the schema.
+pub async fn delete_all_user_profiles(client: &Client) -> Result<TaskInfo, MeilisearchSchemaError> {
+    let index_settings = user_profile_index_settings()?;
+    let index = client.index(&index_settings.name);
+    match index.delete_all_documents().await {
+        Ok(task) => {
+            info!(
+                index = %index_settings.name,
+                task_uid = task.task_uid,
+                "deleted all documents"
+            );
+            Ok(task)
+        }
+        Err(e) => {
+            error!(
+                index = %index_settings.name,
+                error = %e,
+                "failed to delete documents"
+            );
+            Err(MeilisearchSchemaError::Sdk(e))
+        }
+    }
+}
+
+/// Delete user profiles by id; ids that don't exist are ignored
+///
+/// Returns the deletion task.
+pub(crate) async fn delete_user_profiles(
+    client: &Client,
+    ids: &[&str],
+) -> Result<TaskInfo, MeilisearchSchemaError> {
+    let index_settings = user_profile_index_settings()?;
+    let task = client
+        .index(&index_settings.name)
+        .delete_documents(ids)
+        .await
+        .map_err(|e| {
+            error!(index = %index_settings.name, error = %e, "failed to delete documents");
+            MeilisearchSchemaError::Sdk(e)
+        })?;
+    info!(
+        index = %index_settings.name,
+        task_uid = task.task_uid,
+        count = ids.len(),
+        "deleted documents"
+    );
+    Ok(task)
+}
+
+/// Delete every user profile matching a filter expression
+///
+/// Every attribute the filter refers to must be one of the schema's
+/// filterable attributes; otherwise, or if Meilisearch rejects the filter,
+/// a `Schema` error is returned and nothing is deleted. Returns the deletion
+/// task.
+pub async fn delete_user_profiles_by_filter(
+    client: &Client,
+    filter: &str,
+) -> Result<TaskInfo, MeilisearchSchemaError> {
+    if filter.trim().is_empty() {
+        return Err(MeilisearchSchemaError::schema(
+            "Delete filter must not be empty".to_string(),
+        ));
+    }
+
+    let schema = get_user_profile_schema();
+    let filterable = schema.filterable().unwrap_or_default();
+    for field in filter_fields(filter) {
+        if !filterable.contains(&field) {
+            return Err(MeilisearchSchemaError::schema(format!(
+                "Attribute '{}' is not filterable",
+                field
+            )));
+        }
+    }
+
+    let index_settings = user_profile_index_settings()?;
+    let index = client.index(&index_settings.name);
+    let mut query = DocumentDeletionQuery::new(&index);
+    query.with_filter(filter);
+    match index.delete_documents_with(&query).await {
+        Ok(task) => {
+            info!(
+                index = %index_settings.name,
+                task_uid = task.task_uid,
+                filter = %filter,
+                "deleted documents by filter"
+            );
+            Ok(task)
+        }
+        Err(e) if has_error_code(&e, ErrorCode::InvalidDocumentFilter) => {
+            error!(index = %index_settings.name, error = %e, "invalid delete filter");
+            Err(MeilisearchSchemaError::schema(format!(
+                "Invalid delete filter '{}': {}",
+                filter, e
+            )))
+        }
+        Err(e) => {
+            error!(
+                index = %index_settings.name,
+                error = %e,
+                "failed to delete documents by filter"
+            );
+            Err(MeilisearchSchemaError::Sdk(e))
+        }
+    }
+}
+
+/// Delete every user profile last updated before `older_than`
+///
+/// The index must be on schema version 8 or later, which makes `updated_at`
+/// filterable. Returns the deletion task.
+pub async fn delete_stale_user_profiles(
+    client: &Client,
+    older_than: DateTime<Utc>,
+) -> Result<TaskInfo, MeilisearchSchemaError> {
+    // Nothing is older than a cutoff before the epoch
+    let cutoff = u64::try_from(older_than.timestamp()).unwrap_or(0);
+    let filter = FilterExpr::lt("updated_at", cutoff).to_string();
+    delete_user_profiles_by_filter(client, &filter).await
+}
+
+/// Delete the user profiles index entirely, including its settings
+///
+/// The schema version recorded for the index is cleared as well. Returns
+/// `Ok(None)` without enqueuing a deletion if the index doesn't exist, so this
+/// is safe to call as a reset step. Otherwise returns the deletion task.
+pub async fn drop_user_profile_index(
+    client: &Client,
+) -> Result<Option<TaskInfo>, MeilisearchSchemaError> {
+    let index_settings = user_profile_index_settings()?;
+
+    // A dropped index has no schema, even if the index is already gone
+    clear_schema_version(client, &index_settings.name).await?;
+
+    // Deletion is asynchronous on the server, so check for the index first
+    // rather than waiting for the task to fail
+    if let Err(e) = client.get_raw_index(&index_settings.name).await {
+        if is_index_not_found(&e) {
+            info!(
+                index = %index_settings.name,
+                "index does not exist, nothing to drop"
+            );
+            return Ok(None);
+        }
+        return Err(MeilisearchSchemaError::Sdk(e));
+    }
+
+    match client.delete_index(&index_settings.name).await {
+        Ok(task) => {
+            info!(
+                index = %index_settings.name,
+                task_uid = task.task_uid,
+                "dropped index"
+            );
+            forget_max_total_hits(client, &index_settings.name);
+            Ok(Some(task))
+        }
+        Err(e) if is_index_not_found(&e) => Ok(None),
+        Err(e) => {
+            error!(index = %index_settings.name, error = %e, "failed to drop index");
+            Err(MeilisearchSchemaError::Sdk(e))
+        }
+    }
+}
+
+/// Uids of the user profiles index and every index named after it, such as
+/// leftover `user_profiles_tmp` copies, sorted
+pub async fn list_profile_indexes(client: &Client) -> Result<Vec<String>, MeilisearchSchemaError> {
+    let index_settings = user_profile_index_settings()?;
+
+    let mut uids = Vec::new();
+    let mut offset = 0;
+    loop {
+        let mut query = IndexesQuery::new(client);
+        query.with_offset(offset).with_limit(INDEX_LIST_PAGE_SIZE);
+        let page = client.list_all_indexes_with(&query).await.map_err(|e| {
+            error!(error = %e, "failed to list indexes");
+            MeilisearchSchemaError::Sdk(e)
+        })?;
+
+        let len = page.results.len();
+        uids.extend(
+            page.results
+                .into_iter()
+                .map(|index| index.uid)
+                .filter(|uid| uid.starts_with(&index_settings.name)),
+        );
+        if len < INDEX_LIST_PAGE_SIZE {
+            break;
+        }
+        offset += len;
+    }
+
+    uids.sort();
+    Ok(uids)
+}
+
+/// Delete every index left behind by an interrupted rebuild, i.e. any index
+/// whose uid starts with `user_profiles_tmp`
+///
+/// The live user profiles index is never touched. Returns one deletion task
+/// per index, in uid order.
+pub async fn cleanup_orphan_indexes(
+    client: &Client,
+) -> Result<Vec<TaskInfo>, MeilisearchSchemaError> {
+    let index_settings = user_profile_index_settings()?;
+    let prefix = format!("{}_tmp", index_settings.name);
+
+    let mut tasks = Vec::new();
+    for uid in list_profile_indexes(client).await? {
+        if !uid.starts_with(&prefix) {
+            continue;
+        }
+        match client.delete_index(&uid).await {
+            Ok(task) => {
+                info!(index = %uid, task_uid = task.task_uid, "deleted orphaned index");
+                tasks.push(task);
+            }
+            Err(e) => {
+                error!(index = %uid, error = %e, "failed to delete orphaned index");
+                return Err(MeilisearchSchemaError::Sdk(e));
+            }
+        }
+    }
+    Ok(tasks)
+}
+
+/// Apply a partial update to a stored user profile
+///
+/// Fetches the document with the given `id`, merges `partial` into it with
+/// [`UserProfileDocument::merge`], and writes the result back. Fields that are
+/// `None` or empty in `partial` keep their stored values; there is no way to
+/// clear a field through this helper. If no document exists yet, `partial` is
+/// stored as a new document under `id`, so it must carry a fid. Returns the
+/// document that was written.
+///
+/// The read and write are separate requests, so concurrent merges of the same
+/// profile can overwrite each other.
+pub async fn merge_user_profile(
+    client: &Client,
+    id: &str,
+    partial: UserProfileDocument,
+) -> Result<UserProfileDocument, MeilisearchSchemaError> {
+    let index_settings = user_profile_index_settings()?;
+    let index = client.index(&index_settings.name);
+
+    let merged = match index.get_document::<UserProfileDocument>(id).await {
+        Ok(existing) => existing.merge(partial),
+        Err(e) if is_document_not_found(&e) => UserProfileDocument {
+            id: id.to_string(),
+            ..partial
+        }
+        .normalized(),
+        Err(e) => {
+            error!(id = %id, error = %e, "failed to fetch user profile for merge");
+            return Err(MeilisearchSchemaError::Sdk(e));
+        }
+    };
+
+    add_user_profile_documents(client, std::slice::from_ref(&merged)).await?;
+    Ok(merged)
+}
+
+/// Size and indexing status of the user profiles index
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct IndexStats {
+    /// Number of documents in the index
+    pub number_of_documents: usize,
+    /// Whether the index is currently processing a task
+    pub is_indexing: bool,
+    /// Number of documents containing each field
+    pub field_distribution: HashMap<String, usize>,
+}
+
+/// Fetch statistics for the user profiles index
+pub async fn user_profile_index_stats(
+    client: &Client,
+) -> Result<IndexStats, MeilisearchSchemaError> {
+    let index_settings = user_profile_index_settings()?;
+    let index = client.index(&index_settings.name);
+
+    match index.get_stats().await {
+        Ok(stats) => Ok(IndexStats {
+            number_of_documents: stats.number_of_documents,
+            is_indexing: stats.is_indexing,
+            field_distribution: stats.field_distribution,
+        }),
+        Err(e) => {
+            error!(index = %index_settings.name, error = %e, "failed to fetch index stats");
+            Err(MeilisearchSchemaError::Sdk(e))
+        }
+    }
+}
+
+/// Estimate how many bytes of document data the user profiles index holds
+///
+/// This is only an estimate: it serializes a sample of up to
+/// [`SIZE_ESTIMATE_SAMPLE`] documents and scales their average size by the
+/// document count. It measures the documents as JSON, not Meilisearch's
+/// on-disk footprint, which also includes the search structures built from
+/// them.
+pub async fn estimate_index_size(client: &Client) -> Result<u64, MeilisearchSchemaError> {
+    let stats = user_profile_index_stats(client).await?;
+    if stats.number_of_documents == 0 {
+        return Ok(0);
+    }
+
+    let index_settings = user_profile_index_settings()?;
+    let index = client.index(&index_settings.name);
+    let mut query = DocumentsQuery::new(&index);
+    query.with_limit(SIZE_ESTIMATE_SAMPLE);
+    let sample = index
+        .get_documents_with::<UserProfileDocument>(&query)
+        .await
+        .map_err(|e| {
+            error!(index = %index_settings.name, error = %e, "failed to sample documents");
+            MeilisearchSchemaError::Sdk(e)
+        })?
+        .results;
+    if sample.is_empty() {
+        return Ok(0);
+    }
+
+    let mut sample_bytes = 0;
+    for doc in &sample {
+        sample_bytes += serde_json::to_vec(doc)
+            .map_err(|e| MeilisearchSchemaError::Conversion(e.to_string()))?
+            .len() as u64;
+    }
+    let average = sample_bytes as f64 / sample.len() as f64;
+    Ok((average * stats.number_of_documents as f64).round() as u64)
+}
+
+/// Wait until the user profiles index has no enqueued or processing task
+///
+/// Polls the task queue every [`INDEXING_POLL_INTERVAL`], so writes that were
+/// enqueued but not yet picked up are waited for as well. Fails with
+/// `Client("timeout")` if tasks are still pending after `timeout`. This
+/// doesn't report whether the tasks succeeded; use [`await_task`] for that.
+pub async fn wait_until_indexed(
+    client: &Client,
+    timeout: Duration,
+) -> Result<(), MeilisearchSchemaError> {
+    let index_settings = user_profile_index_settings()?;
+    with_timeout(timeout, async {
+        loop {
+            let mut query = TasksSearchQuery::new(client);
+            query
+                .with_index_uids([index_settings.name.as_str()])
+                .with_statuses(["enqueued", "processing"])
+                .with_limit(1);
+            let pending = client.get_tasks_with(&query).await.map_err(|e| {
+                error!(index = %index_settings.name, error = %e, "failed to list pending tasks");
+                MeilisearchSchemaError::Sdk(e)
+            })?;
+            if pending.results.is_empty() {
+                return Ok(());
+            }
+            tokio::time::sleep(INDEXING_POLL_INTERVAL).await;
+        }
+    })
+    .await
+}
+
+/// Fetch several user profiles by id
+///
+/// Results are returned in the same order as `ids`, with `None` for ids that
+/// have no document; repeated ids get the same profile. The documents are
+/// fetched with an `id IN [...]` filter, one request per chunk of ids, which
+/// relies on `id` being filterable as it is in the predefined schema.
+pub async fn get_user_profiles_by_ids(
+    client: &Client,
+    ids: &[&str],
+) -> Result<Vec<Option<UserProfile>>, MeilisearchSchemaError> {
+    let index_settings = user_profile_index_settings()?;
+    let index = client.index(&index_settings.name);
+
+    let mut unique: Vec<&str> = ids.to_vec();
+    unique.sort_unstable();
+    unique.dedup();
+
+    let mut found: HashMap<String, UserProfileDocument> = HashMap::with_capacity(unique.len());
+    for chunk in unique.chunks(ID_FILTER_CHUNK_SIZE) {
+        let filter = FilterExpr::is_in("id", chunk.iter().copied()).to_string();
+
+        // Ids are unique, so a chunk never matches more documents than it has ids
+        let mut query = DocumentsQuery::new(&index);
+        query.with_filter(&filter).with_limit(chunk.len());
+
+        let page = index
+            .get_documents_with::<UserProfileDocument>(&query)
+            .await
+            .map_err(|e| {
+                error!(error = %e, "failed to fetch user profiles by id");
+                MeilisearchSchemaError::Sdk(e)
+            })?;
+        found.extend(page.results.into_iter().map(|doc| (doc.id.clone(), doc)));
+    }
+
+    Ok(ids
+        .iter()
+        .map(|id| found.get(*id).cloned().map(UserProfile::from))
+        .collect())
+}
+
+/// Find which of the given fids already have a profile in the index
+///
+/// Fids are checked in chunks so the `fid IN [...]` filter stays small no
+/// matter how many are passed.
+pub async fn existing_fids(
+    client: &Client,
+    fids: &[Fid],
+) -> Result<HashSet<Fid>, MeilisearchSchemaError> {
+    #[derive(Deserialize)]
+    struct FidOnly {
+        fid: u64,
+    }
+
+    let docs: Vec<FidOnly> = documents_with_fids(client, fids, Some(&["fid"])).await?;
+    let existing: HashSet<Fid> = docs
+        .into_iter()
+        .filter_map(|doc| Fid::new(doc.fid).ok())
+        .collect();
+
+    info!(
+        checked = fids.len(),
+        existing = existing.len(),
+        "checked existing fids"
+    );
+    Ok(existing)
+}
+
+/// Fetch the profiles for a list of fids, in the order the fids are given
+///
+/// Meilisearch returns filtered documents in index order, so the results are
+/// reordered to match `fids`. Fids without a profile are skipped, repeated
+/// fids are only returned once, and several profiles sharing a fid are
+/// returned together, ordered by id.
+pub async fn get_profiles_by_fids_ordered(
+    client: &Client,
+    fids: &[Fid],
+) -> Result<Vec<UserProfile>, MeilisearchSchemaError> {
+    let docs: Vec<UserProfileDocument> = documents_with_fids(client, fids, None).await?;
+
+    let mut by_fid: HashMap<u64, Vec<UserProfile>> = HashMap::new();
+    for profile in documents_to_profiles(docs) {
+        by_fid.entry(profile.fid).or_default().push(profile);
+    }
+
+    let mut ordered = Vec::new();
+    for fid in fids {
+        if let Some(mut profiles) = by_fid.remove(&fid.as_u64()) {
+            profiles.sort_by(|a, b| a.id.cmp(&b.id));
+            ordered.extend(profiles);
+        }
+    }
+    Ok(ordered)
+}
+
+/// Fetch every document whose fid is one of `fids`, optionally only the
+/// given fields
+///
+/// Fids are looked up in chunks so the `fid IN [...]` filter stays small no
+/// matter how many are passed, and each chunk is paged since several
+/// documents may share a fid.
+async fn documents_with_fids<T>(
+    client: &Client,
+    fids: &[Fid],
+    fields: Option<&[&str]>,
+) -> Result<Vec<T>, MeilisearchSchemaError>
+where
+    T: DeserializeOwned + Send + Sync + 'static,
+{
+    let index_settings = user_profile_index_settings()?;
+    let index = client.index(&index_settings.name);
+
+    let mut unique: Vec<Fid> = fids.to_vec();
+    unique.sort_unstable();
+    unique.dedup();
+
+    let mut docs = Vec::new();
+    for chunk in unique.chunks(FID_FILTER_CHUNK_SIZE) {
+        let filter = ProfileFilter::new().fid_in(chunk).build();
+
+        let mut offset = 0;
+        loop {
+            let mut query = DocumentsQuery::new(&index);
+            query
+                .with_filter(&filter)
+                .with_offset(offset)
+                .with_limit(FID_FILTER_CHUNK_SIZE);
+            if let Some(fields) = fields {
+                query.with_fields(fields.iter().copied());
+            }
+
+            let page = index.get_documents_with::<T>(&query).await.map_err(|e| {
+                error!(error = %e, "failed to fetch documents by fid");
+                MeilisearchSchemaError::Sdk(e)
+            })?;
+
+            let len = page.results.len();
+            docs.extend(page.results);
+            if len < FID_FILTER_CHUNK_SIZE {
+                break;
+            }
+            offset += len;
+        }
+    }
+
+    Ok(docs)
+}
+
+/// Fetch every user profile whose username is `username`, ignoring case
+///
+/// Usernames aren't guaranteed to be unique across documents, so all matches
+/// are returned, up to a limit of 100. Matching uses `username_normalized`,
+/// so documents indexed before that field existed must be re-added to be
+/// found.
+pub async fn get_user_profile_by_username(
+    client: &Client,
+    username: &str,
+) -> Result<Vec<UserProfile>, MeilisearchSchemaError> {
+    let index_settings = user_profile_index_settings()?;
+    let index = client.index(&index_settings.name);
+
+    let filter = FilterExpr::eq("username_normalized", normalize_username(username)).to_string();
+    let mut query = DocumentsQuery::new(&index);
+    query.with_filter(&filter).with_limit(MAX_USERNAME_MATCHES);
+
+    match index
+        .get_documents_with::<UserProfileDocument>(&query)
+        .await
+    {
+        Ok(page) => Ok(documents_to_profiles(page.results)),
+        Err(e) => {
+            error!(
+                username = %username,
+                error = %e,
+                "failed to look up user profile by username"
+            );
+            Err(MeilisearchSchemaError::Sdk(e))
+        }
+    }
+}
+
+/// Fetch every user profile with `address` among its wallet addresses
+///
+/// Matching ignores checksum casing: the address is normalized and compared
+/// against `wallet_addresses_normalized`. On an array field, Meilisearch's `=`
+/// filter matches when any element equals the value, so
+/// `wallet_addresses_normalized = "0x.."` finds profiles holding that address
+/// next to others. Several profiles can list the same wallet, so all matches are
+/// returned, up to a limit of 100.
+pub async fn get_profile_by_wallet(
+    client: &Client,
+    address: &str,
+) -> Result<Vec<UserProfile>, MeilisearchSchemaError> {
+    let index_settings = user_profile_index_settings()?;
+    let index = client.index(&index_settings.name);
+
+    let filter = FilterExpr::eq(
+        "wallet_addresses_normalized",
+        normalize_wallet_address(address),
+    )
+    .to_string();
+    let mut query = DocumentsQuery::new(&index);
+    query.with_filter(&filter).with_limit(MAX_WALLET_MATCHES);
+
+    match index
+        .get_documents_with::<UserProfileDocument>(&query)
+        .await
+    {
+        Ok(page) => Ok(documents_to_profiles(page.results)),
+        Err(e) => {
+            error!(
+                address = %address,
+                error = %e,
+                "failed to look up user profiles by wallet address"
+            );
+            Err(MeilisearchSchemaError::Sdk(e))
+        }
+    }
+}
+
+/// Optional parameters for [`search_user_profiles_with`]
+#[derive(Debug, Clone, Default)]
+pub struct SearchOptions<'a> {
+    /// Maximum number of results
+    pub limit: Option<usize>,
+    /// Number of results to skip
+    pub offset: Option<usize>,
+    /// Meilisearch filter expression; build it with
+    /// [`ProfileFilter`](crate::helpers::filter::ProfileFilter) or
+    /// [`FilterExpr`](crate::helpers::filter::FilterExpr) rather than by hand
+    /// when any part of it comes from user input
+    pub filter: Option<&'a str>,
+    /// Fields to return for each hit, to reduce response size. See
+    /// [`UserProfileDocument`] for which fields are safe to omit.
+    pub attributes_to_retrieve: Option<&'a [&'a str]>,
+    /// Attribute to deduplicate results on instead of the index's distinct
+    /// attribute; must be filterable
+    pub distinct: Option<&'a str>,
+    /// Sort criteria such as `follower_count:desc`, applied in order so each
+    /// one breaks ties left by the ones before it; each field must be
+    /// sortable
+    pub sort: Option<&'a [&'a str]>,
+    /// Ask Meilisearch for each hit's ranking score; only reported by
+    /// [`search_user_profiles_scored`]
+    pub show_ranking_score: bool,
+    /// How many query terms a document must match; `None` uses the
+    /// Meilisearch default
+    pub matching_strategy: Option<MatchingStrategy>,
+    /// Only match the query against these attributes; each must be searchable
+    pub search_on: Option<&'a [&'a str]>,
+}
+
+/// Which query terms a document must contain to match
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchingStrategy {
+    /// Every query term must match
+    All,
+    /// Drop terms from the end of the query until documents match
+    Last,
+    /// Drop the most frequent terms first until documents match
+    Frequency,
+}
+
+impl From<MatchingStrategy> for MatchingStrategies {
+    fn from(strategy: MatchingStrategy) -> Self {
+        match strategy {
+            MatchingStrategy::All => Self::ALL,
+            MatchingStrategy::Last => Self::LAST,
+            MatchingStrategy::Frequency => Self::FREQUENCY,
+        }
+    }
+}
+
+/// A search hit together with its relevance
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoredUserProfile {
+    pub profile: UserProfile,
+    /// Meilisearch's `_rankingScore` between 0 and 1, or `None` unless
+    /// `show_ranking_score` was set
+    pub ranking_score: Option<f64>,
+}
+
+/// Search for user profiles
+///
+/// `filter` is a Meilisearch filter expression; build it with
+/// [`ProfileFilter`](crate::helpers::filter::ProfileFilter) rather than by hand
+/// when any part of it comes from user input.
+pub async fn search_user_profiles(
+    client: &Client,
+    query: &str,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    filter: Option<&str>,
+) -> Result<Vec<UserProfile>, MeilisearchSchemaError> {
+    let options = SearchOptions {
+        limit,
+        offset,
+        filter,
+        ..Default::default()
+    };
+    search_user_profiles_with(client, query, &options).await
+}
+
+/// List user profiles without a query, in a stable order
+///
+/// Use this for "browse all" views, and [`search_user_profiles`] whenever
+/// there is query text. An empty search query matches every document but
+/// still runs the ranking rules, so the order of equally-ranked profiles can
+/// shift between requests as the index changes.
+///
+/// Without `sort`, profiles are read straight from the documents endpoint in
+/// index order. With `sort`, Meilisearch's documents endpoint can't sort, so
+/// a placeholder search is used instead; each field must then be sortable
+/// and ties are broken by index order. Either way every matching profile is
+/// listed, including ones the index's distinct attribute would collapse.
+pub async fn browse_user_profiles(
+    client: &Client,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    sort: Option<&[&str]>,
+    filter: Option<&str>,
+) -> Result<Vec<UserProfile>, MeilisearchSchemaError> {
+    let index_settings = user_profile_index_settings()?;
+
+    if sort.is_some() {
+        let options = SearchOptions {
+            limit,
+            offset,
+            filter,
+            sort,
+            distinct: Some(&index_settings.primary_key),
+            ..Default::default()
+        };
+        return search_user_profiles_with(client, "", &options).await;
+    }
+
+    let index = client.index(&index_settings.name);
+    let mut query = DocumentsQuery::new(&index);
+    if let Some(limit) = limit {
+        query.with_limit(limit);
+    }
+    if let Some(offset) = offset {
+        query.with_offset(offset);
+    }
+    if let Some(filter) = filter {
+        query.with_filter(filter);
+    }
+
+    match index
+        .get_documents_with::<UserProfileDocument>(&query)
+        .await
+    {
+        Ok(page) => Ok(documents_to_profiles(page.results)),
+        Err(e) => {
+            error!(error = %e, "failed to browse user profiles");
+            Err(MeilisearchSchemaError::Sdk(e))
+        }
+    }
+}
+
+/// Search for user profiles, overriding the index's distinct attribute
+///
+/// The schema deduplicates on `username`, which hides profiles sharing a
+/// username. `Some(attribute)` deduplicates on that attribute instead, and
+/// `None` returns every match by deduplicating on the always-unique primary
+/// key. The index setting itself is never changed, so concurrent searches are
+/// unaffected.
+pub async fn search_user_profiles_with_distinct(
+    client: &Client,
+    query: &str,
+    distinct: Option<&str>,
+) -> Result<Vec<UserProfile>, MeilisearchSchemaError> {
+    let index_settings = user_profile_index_settings()?;
+    let options = SearchOptions {
+        distinct: Some(distinct.unwrap_or(&index_settings.primary_key)),
+        ..Default::default()
+    };
+    search_user_profiles_with(client, query, &options).await
+}
+
+/// Get the primary key the server has for the user profiles index
+///
+/// `None` means the index exists but has no primary key yet.
+pub async fn get_index_primary_key(
+    client: &Client,
+) -> Result<Option<String>, MeilisearchSchemaError> {
+    let index_settings = user_profile_index_settings()?;
+    client
+        .get_index(&index_settings.name)
+        .await
+        .map(|index| index.primary_key)
+        .map_err(|e| {
+            error!(index = %index_settings.name, error = %e, "failed to get index");
+            MeilisearchSchemaError::Sdk(e)
+        })
+}
+
+/// Get the distinct attribute currently set on the user profiles index
+pub async fn user_profile_distinct_attribute(
+    client: &Client,
+) -> Result<Option<String>, MeilisearchSchemaError> {
+    let index_settings = user_profile_index_settings()?;
+    client
+        .index(&index_settings.name)
+        .get_distinct_attribute()
+        .await
+        .map_err(|e| {
+            error!(index = %index_settings.name, error = %e, "failed to get distinct attribute");
+            MeilisearchSchemaError::Sdk(e)
+        })
+}
+
+/// Search for user profiles, treating a missing index as having no results
+///
+/// Unlike [`search_user_profiles`], which fails with `index_not_found` until
+/// the index has been created, this returns an empty list in that case, so
+/// callers can render normally right after a fresh deploy. Every other error
+/// is still returned.
+pub async fn search_user_profiles_lenient(
+    client: &Client,
+    query: &str,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    filter: Option<&str>,
+) -> Result<Vec<UserProfile>, MeilisearchSchemaError> {
+    match search_user_profiles(client, query, limit, offset, filter).await {
+        Err(MeilisearchSchemaError::Sdk(e)) if is_index_not_found(&e) => {
+            info!("user profiles index not found, returning no results");
+            Ok(Vec::new())
+        }
+        result => result,
+    }
+}
+
+/// [`search_user_profiles`], giving up after `timeout`
+pub async fn search_user_profiles_with_timeout(
+    client: &Client,
+    query: &str,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    filter: Option<&str>,
+    timeout: Duration,
+) -> Result<Vec<UserProfile>, MeilisearchSchemaError> {
+    with_timeout(
+        timeout,
+        search_user_profiles(client, query, limit, offset, filter),
+    )
+    .await
+}
+
+/// Search for user profiles with the full set of search options
+pub async fn search_user_profiles_with(
+    client: &Client,
+    query: &str,
+    options: &SearchOptions<'_>,
+) -> Result<Vec<UserProfile>, MeilisearchSchemaError> {
+    let hits = search_user_profiles_scored(client, query, options).await?;
+    Ok(hits.into_iter().map(|hit| hit.profile).collect())
+}
+
+/// Search for user profiles, returning each hit's ranking score as well
+///
+/// Scores are only computed when `options.show_ranking_score` is set;
+/// otherwise every `ranking_score` is `None`.
+pub async fn search_user_profiles_scored(
+    client: &Client,
+    query: &str,
+    options: &SearchOptions<'_>,
+) -> Result<Vec<ScoredUserProfile>, MeilisearchSchemaError> {
+    let hits = search_user_profile_hits(client, query, options).await?;
+
+    // Convert documents back to proto messages
+    Ok(hits
+        .into_iter()
+        .map(|hit| ScoredUserProfile {
+            profile: UserProfile::from(hit.result),
+            ranking_score: hit.ranking_score,
+        })
+        .collect())
+}
+
+/// Search for user profiles, returning each stored document alongside the
+/// profile converted from it
+///
+/// Meant for debugging conversions, such as spotting a stored `updated_at`
+/// that couldn't be parsed and reads as 0 on both.
+pub async fn search_user_profiles_raw(
+    client: &Client,
+    query: &str,
+    options: &SearchOptions<'_>,
+) -> Result<Vec<(UserProfileDocument, UserProfile)>, MeilisearchSchemaError> {
+    let hits = search_user_profile_hits(client, query, options).await?;
+    Ok(hits
+        .into_iter()
+        .map(|hit| (hit.result.clone(), UserProfile::from(hit.result)))
+        .collect())
+}
+
+/// Find profiles whose display name and bio closely match `profile`'s
+///
+/// Searches for the profile's display name and bio and keeps hits with a
+/// ranking score of at least `threshold`, which must be between 0 and 1.
+/// The profile itself is excluded, and at most [`MAX_DUPLICATE_CANDIDATES`]
+/// candidates are returned, best match first. A profile with neither field
+/// set has no candidates.
+pub async fn find_duplicate_profiles(
+    client: &Client,
+    profile: &UserProfile,
+    threshold: f64,
+) -> Result<Vec<UserProfile>, MeilisearchSchemaError> {
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(MeilisearchSchemaError::schema(format!(
+            "Duplicate threshold must be between 0 and 1, got {}",
+            threshold
+        )));
+    }
+
+    let query = [&profile.display_name, &profile.bio]
+        .into_iter()
+        .flatten()
+        .map(|value| value.trim())
+        .filter(|value| !value.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let index_settings = user_profile_index_settings()?;
+    let filter = FilterExpr::ne("id", profile.id.as_str()).to_string();
+    let options = SearchOptions {
+        limit: Some(MAX_DUPLICATE_CANDIDATES),
+        filter: Some(&filter),
+        // Copies often reuse the username too, so don't collapse them
+        distinct: Some(&index_settings.primary_key),
+        show_ranking_score: true,
+        search_on: Some(&["display_name", "bio"]),
+        ..Default::default()
+    };
+
+    let hits = search_user_profiles_scored(client, &query, &options).await?;
+    Ok(hits
+        .into_iter()
+        .filter(|hit| hit.ranking_score.is_some_and(|score| score >= threshold))
+        .map(|hit| hit.profile)
+        .collect())
+}
+
+/// Run a user profile search and return the raw hits
+async fn search_user_profile_hits(
+    client: &Client,
+    query: &str,
+    options: &SearchOptions<'_>,
+) -> Result<Vec<SearchResult<UserProfileDocument>>, MeilisearchSchemaError> {
+    let index_settings = user_profile_index_settings()?;
+    let index = client.index(&index_settings.name);
+
+    // Create search query
+    let mut search = index.search();
+    search.with_query(query);
+
+    if let Some(limit_val) = options.limit {
+        search.with_limit(limit_val);
+    }
+
+    if let Some(offset_val) = options.offset {
+        search.with_offset(offset_val);
+    }
+
+    if let Some(filter_val) = options.filter {
+        search.with_filter(filter_val);
+    }
+
+    if let Some(attributes) = options.attributes_to_retrieve {
+        search.with_attributes_to_retrieve(Selectors::Some(attributes));
+    }
+
+    if let Some(distinct) = options.distinct {
+        search.with_distinct(distinct);
+    }
+
+    if let Some(sort) = options.sort {
+        check_sort(sort)?;
+        search.with_sort(sort);
+    }
+
+    if options.show_ranking_score {
+        search.with_show_ranking_score(true);
+    }
+
+    if let Some(strategy) = options.matching_strategy {
+        search.with_matching_strategy(strategy.into());
+    }
+
+    if let Some(attributes) = options.search_on {
+        let schema = get_user_profile_schema();
+        let searchable = schema.searchable().unwrap_or_default();
+        if let Some(field) = attributes
+            .iter()
+            .find(|field| !searchable.iter().any(|s| s == *field))
+        {
+            return Err(MeilisearchSchemaError::schema(format!(
+                "Attribute '{}' is not searchable",
+                field
+            )));
+        }
+        search.with_attributes_to_search_on(attributes);
+    }
+
+    warn_if_past_max_total_hits(client, options).await;
+
+    // Execute search
+    match search.execute::<UserProfileDocument>().await {
+        Ok(results) => Ok(results.hits),
+        Err(e) => {
+            error!(error = %e, "failed to search user profiles");
+            Err(unconfigured_attribute_error(&e).unwrap_or(MeilisearchSchemaError::Sdk(e)))
+        }
+    }
+}
+
+/// Check that every sort criterion is `field:asc` or `field:desc` on a
+/// sortable field of the user profile schema
+///
+/// `_geoPoint(lat, lng):asc` sorts on `_geo`.
+fn check_sort(sort: &[&str]) -> Result<(), MeilisearchSchemaError> {
+    let schema = get_user_profile_schema();
+    let sortable = schema.sortable().unwrap_or_default();
+    for criterion in sort {
+        let field = match criterion.rsplit_once(':') {
+            Some((field, "asc" | "desc")) if field.starts_with("_geoPoint(") => "_geo",
+            Some((field, "asc" | "desc")) if !field.is_empty() => field,
+            _ => {
+                return Err(MeilisearchSchemaError::schema(format!(
+                    "Invalid sort '{}': expected 'field:asc' or 'field:desc'",
+                    criterion
+                )));
+            }
+        };
+        if !sortable.iter().any(|s| s == field) {
+            return Err(MeilisearchSchemaError::schema(format!(
+                "field '{}' is not sortable",
+                field
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Number of hits Meilisearch returns when a search sets no limit
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+/// How long a fetched `max_total_hits` is trusted before it is fetched again
+///
+/// This bounds how stale the cache gets when the settings are changed by
+/// something other than this crate.
+const MAX_TOTAL_HITS_TTL: Duration = Duration::from_secs(60);
+
+/// `max_total_hits` of each searched index and when it was fetched, keyed by
+/// host and index name
+static MAX_TOTAL_HITS: OnceLock<Mutex<HashMap<String, (Instant, usize)>>> = OnceLock::new();
+
+fn max_total_hits_key(client: &Client, index_name: &str) -> String {
+    format!("{}/{}", client.get_host(), index_name)
+}
+
+/// The cached `max_total_hits` under `key`, unless it is missing or older
+/// than [`MAX_TOTAL_HITS_TTL`]
+fn cached_max_total_hits(key: &str) -> Option<usize> {
+    let cache = MAX_TOTAL_HITS.get()?;
+    let cache = cache.lock().unwrap_or_else(PoisonError::into_inner);
+    let &(fetched_at, max_total_hits) = cache.get(key)?;
+    (fetched_at.elapsed() < MAX_TOTAL_HITS_TTL).then_some(max_total_hits)
+}
+
+/// Drop the cached `max_total_hits` of an index whose settings may have
+/// changed, so the next search fetches it again
+fn forget_max_total_hits(client: &Client, index_name: &str) {
+    if let Some(cache) = MAX_TOTAL_HITS.get() {
+        cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&max_total_hits_key(client, index_name));
+    }
+}
+
+/// Warn when a search pages past the index's `max_total_hits`
+///
+/// Meilisearch never returns hits beyond that limit, so such a search quietly
+/// comes back short or empty. The limit is cached per host and index for
+/// [`MAX_TOTAL_HITS_TTL`], and the entry is dropped whenever this crate changes
+/// the index's settings or drops it. If the limit can't be fetched, including
+/// because the index doesn't exist yet, no check is made and nothing is
+/// cached.
+async fn warn_if_past_max_total_hits(client: &Client, options: &SearchOptions<'_>) {
+    let offset = options.offset.unwrap_or(0);
+    let limit = options.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    let Ok(index_settings) = user_profile_index_settings() else {
+        return;
+    };
+    let key = max_total_hits_key(client, &index_settings.name);
+
+    let max_total_hits = match cached_max_total_hits(&key) {
+        Some(max_total_hits) => max_total_hits,
+        None => {
+            let Ok(pagination) = client.index(&index_settings.name).get_pagination().await else {
+                return;
+            };
+            MAX_TOTAL_HITS
+                .get_or_init(Default::default)
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(key, (Instant::now(), pagination.max_total_hits));
+            pagination.max_total_hits
+        }
+    };
+
+    if offset.saturating_add(limit) > max_total_hits {
+        warn!(
+            offset,
+            limit,
+            max_total_hits,
+            "search pages past max_total_hits; hits beyond it are never returned"
+        );
+    }
+}
+
+/// Turn a search error about filtering or sorting on an attribute the index
+/// isn't configured for into a short `Schema` error naming the attribute
+///
+/// Meilisearch reports these as e.g. ``Attribute `bio` is not filterable.``
+/// followed by the list of configured attributes. Returns `None` for any
+/// other error, including filter syntax errors.
+fn unconfigured_attribute_error(err: &SdkError) -> Option<MeilisearchSchemaError> {
+    let SdkError::Meilisearch(e) = err else {
+        return None;
+    };
+    let kind = match e.error_code {
+        ErrorCode::InvalidSearchFilter => "filterable",
+        ErrorCode::InvalidSearchSort => "sortable",
+        _ => return None,
+    };
+
+    let (field, rest) = e
+        .error_message
+        .strip_prefix("Attribute `")?
+        .split_once('`')?;
+    if !rest.starts_with(&format!(" is not {}", kind)) {
+        return None;
+    }
+    Some(MeilisearchSchemaError::schema(format!(
+        "field '{}' is not {}",
+        field, kind
+    )))
+}
+
+/// Run several profile searches in a single multi-search request
+///
+/// Returns one result list per query, in the same order as `queries`.
+pub async fn multi_search_user_profiles(
+    client: &Client,
+    queries: &[&str],
+    limit_each: usize,
+) -> Result<Vec<Vec<UserProfile>>, MeilisearchSchemaError> {
+    if queries.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let index_settings = user_profile_index_settings()?;
+    let index = client.index(&index_settings.name);
+
+    let mut multi_search = client.multi_search();
+    for query in queries {
+        let mut search = index.search();
+        search.with_query(query).with_limit(limit_each);
+        multi_search.with_search_query(search);
+    }
+
+    let response = multi_search
+        .execute::<UserProfileDocument>()
+        .await
+        .map_err(|e| {
+            error!(error = %e, queries = queries.len(), "failed to multi-search user profiles");
+            MeilisearchSchemaError::Sdk(e)
+        })?;
+
+    // Meilisearch answers multi-search queries in request order
+    Ok(response
+        .results
+        .into_iter()
+        .map(|results| {
+            results
+                .hits
+                .into_iter()
+                .map(|hit| UserProfile::from(hit.result))
+                .collect()
+        })
+        .collect())
+}
+
+/// Find user profiles within `radius_m` meters of a point, nearest first
+///
+/// Only profiles with a `geo` location are considered. Fails with
+/// `InvalidFilter` if the point is off the map.
+pub async fn search_user_profiles_near(
+    client: &Client,
+    lat: f64,
+    lng: f64,
+    radius_m: u64,
+    limit: Option<usize>,
+) -> Result<Vec<UserProfile>, MeilisearchSchemaError> {
+    let index_settings = user_profile_index_settings()?;
+    let index = client.index(&index_settings.name);
+
+    let filter = ProfileFilter::new()
+        .within_radius(lat, lng, radius_m)?
+        .build();
+    let sort_by_distance = format!("_geoPoint({}, {}):asc", lat, lng);
+    let sort = [sort_by_distance.as_str()];
+
+    let mut search = index.search();
+    search.with_filter(&filter).with_sort(&sort);
+
+    if let Some(limit_val) = limit {
+        search.with_limit(limit_val);
+    }
+
+    match search.execute::<UserProfileDocument>().await {
+        Ok(results) => Ok(results
+            .hits
+            .into_iter()
+            .map(|hit| UserProfile::from(hit.result))
+            .collect()),
+        Err(e) => {
+            error!(error = %e, "failed to search user profiles by location");
+            Err(MeilisearchSchemaError::Sdk(e))
+        }
+    }
+}
+
+/// Suggest user profiles whose username or display name starts with `prefix`
+///
+/// Only `username` and `display_name` are searched, and at most
+/// [`MAX_AUTOCOMPLETE_LIMIT`] results are returned regardless of `limit`.
+/// Meilisearch treats the last query word as a prefix, so partially typed
+/// names match. A blank prefix returns no results.
+pub async fn autocomplete_usernames(
+    client: &Client,
+    prefix: &str,
+    limit: usize,
+) -> Result<Vec<UserProfile>, MeilisearchSchemaError> {
+    let prefix = prefix.trim();
+    if prefix.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let index_settings = user_profile_index_settings()?;
+    let index = client.index(&index_settings.name);
+
+    let mut search = index.search();
+    search
+        .with_query(prefix)
+        .with_attributes_to_search_on(&["username", "display_name"])
+        .with_limit(limit.min(MAX_AUTOCOMPLETE_LIMIT));
+
+    match search.execute::<UserProfileDocument>().await {
+        Ok(results) => Ok(results
+            .hits
+            .into_iter()
+            .map(|hit| UserProfile::from(hit.result))
+            .collect()),
+        Err(e) => {
+            error!(error = %e, "failed to autocomplete usernames");
+            Err(MeilisearchSchemaError::Sdk(e))
+        }
+    }
+}
+
+/// Search user profiles by vector similarity
+///
+/// `embedder` names an embedder configured on the live index, for example
+/// with [`UserProfileSchemaBuilder::add_embedder`]; the predefined schema
+/// defines none. `vector` must match its dimensions. Meilisearch rejects the
+/// search if the index has no embedder by that name.
+pub async fn search_user_profiles_semantic(
+    client: &Client,
+    embedder: &str,
+    vector: Vec<f32>,
+    limit: Option<usize>,
+) -> Result<Vec<UserProfile>, MeilisearchSchemaError> {
+    if embedder.trim().is_empty() {
+        return Err(MeilisearchSchemaError::schema(
+            "Embedder name must not be empty".to_string(),
+        ));
+    }
+    let index_settings = user_profile_index_settings()?;
+
+    let mut body = serde_json::json!({
+        "vector": vector,
+        "hybrid": {
+            "embedder": embedder,
+            "semanticRatio": 1.0,
+        },
+    });
+    if let Some(limit_val) = limit {
+        body["limit"] = serde_json::Value::from(limit_val);
+    }
+
+    let http_client = ReqwestClient::new(client.get_api_key())?;
+    let results = http_client
+        .request::<(), _, VectorSearchResults>(
+            &format!(
+                "{}/indexes/{}/search",
+                client.get_host(),
+                index_settings.name
+            ),
+            Method::Post { query: (), body },
+            200,
+        )
+        .await
+        .map_err(|e| {
+            error!(
+                embedder = %embedder,
+                error = %e,
+                "failed to run semantic search on user profiles"
+            );
+            MeilisearchSchemaError::Sdk(e)
+        })?;
+
+    Ok(results.hits.into_iter().map(UserProfile::from).collect())
+}
+
+/// Hits of a vector search request
+#[derive(Debug, Deserialize)]
+struct VectorSearchResults {
+    hits: Vec<UserProfileDocument>,
+}
+
+/// Iterate over every user profile in the index
+///
+/// Documents are fetched in pages of `page_size` (a size of 0 is treated as 1)
+/// using offset/limit paging, so the stream ends after the first short page.
+/// Offset paging is not a snapshot: documents added or deleted while the stream
+/// is being consumed can shift page boundaries, causing profiles to be skipped
+/// or yielded twice. Pause writes during a full export if that matters.
+pub fn iter_all_user_profiles(
+    client: &Client,
+    page_size: usize,
+) -> impl Stream<Item = Result<UserProfile, MeilisearchSchemaError>> {
+    user_profile_document_pages(client, page_size)
+        .map_ok(|page| stream::iter(page.into_iter().map(|doc| Ok(UserProfile::from(doc)))))
+        .try_flatten()
+}
+
+/// Stream pages of raw documents from the user profiles index
+///
+/// If the schema can't be loaded, the stream yields that error and ends.
+pub(crate) fn user_profile_document_pages(
+    client: &Client,
+    page_size: usize,
+) -> impl Stream<Item = Result<Vec<UserProfileDocument>, MeilisearchSchemaError>> {
+    let index = match user_profile_index_settings() {
+        Ok(index_settings) => client.index(&index_settings.name),
+        Err(e) => return stream::once(async { Err(e) }).left_stream(),
+    };
+    let page_size = page_size.max(1);
+
+    stream::try_unfold(Some(0), move |offset| {
+        let index = index.clone();
+        async move {
+            let Some(offset) = offset else {
+                return Ok(None);
+            };
+
+            let mut query = DocumentsQuery::new(&index);
+            query.with_offset(offset).with_limit(page_size);
+
+            let page = index
+                .get_documents_with::<UserProfileDocument>(&query)
+                .await
+                .map_err(|e| {
+                    error!(offset, error = %e, "failed to fetch user profiles");
+                    MeilisearchSchemaError::Sdk(e)
+                })?;
+
+            if page.results.is_empty() {
+                return Ok(None);
+            }
+
+            // A short page means we've reached the end of the index
+            let next = if page.results.len() < page_size {
+                None
+            } else {
+                Some(offset + page.results.len())
+            };
+
+            Ok(Some((page.results, next)))
+        }
+    })
+    .right_stream()
+}
+
+/// Create an API key that can only search the user profiles index
+///
+/// The key can be handed to clients that should never see the master key.
+/// Without `expires_at` it stays valid until deleted. Returns the key itself.
+pub async fn create_search_key(
+    client: &Client,
+    description: &str,
+    expires_at: Option<DateTime<Utc>>,
+) -> Result<String, MeilisearchSchemaError> {
+    let index_settings = user_profile_index_settings()?;
+
+    let mut builder = KeyBuilder::new();
+    builder
+        .with_action(Action::Search)
+        .with_index(&index_settings.name)
+        .with_description(description);
+    if let Some(expires_at) = expires_at {
+        let expires_at = OffsetDateTime::from_unix_timestamp(expires_at.timestamp())
+            .map_err(|e| MeilisearchSchemaError::Conversion(e.to_string()))?;
+        builder.with_expires_at(expires_at);
+    }
+
+    match builder.execute(client).await {
+        Ok(key) => {
+            info!(index = %index_settings.name, uid = %key.uid, "created search key");
+            Ok(key.key)
+        }
+        Err(e) => {
+            error!(index = %index_settings.name, error = %e, "failed to create search key");
+            Err(MeilisearchSchemaError::Sdk(e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Far enough out to overflow 32-bit seconds and four-digit years
+    const FAR_FUTURE: u64 = 253_402_300_800 * 4;
+
+    fn meilisearch_error(code: ErrorCode, message: &str) -> SdkError {
+        SdkError::Meilisearch(MeilisearchError {
+            error_message: message.to_string(),
+            error_code: code,
+            error_type: ErrorType::InvalidRequest,
+            error_link: String::new(),
+        })
+    }
+
+    #[test]
+    fn failed_tasks_are_schema_errors_with_the_server_details() {
+        let err = MeilisearchSchemaError::failed_task(
+            7,
+            MeilisearchError {
+                error_message: "Index `user_profiles` not found.".to_string(),
+                error_code: ErrorCode::IndexNotFound,
+                error_type: ErrorType::InvalidRequest,
+                error_link: "https://docs.meilisearch.com/errors#index_not_found".to_string(),
+            },
+        );
+
+        let MeilisearchSchemaError::Schema {
+            message,
+            task: Some(task),
+        } = &err
+        else {
+            panic!("expected a Schema error with task details, got {:?}", err);
+        };
+        assert_eq!(task.task_uid, 7);
+        assert_eq!(task.error_type, ErrorType::InvalidRequest);
+        assert!(
+            message.contains("Index `user_profiles` not found."),
+            "{}",
+            message
+        );
+        assert!(message.contains(&task.link), "{}", message);
+        assert_eq!(err.meilisearch_code(), Some(&ErrorCode::IndexNotFound));
+    }
+
+    #[test]
+    fn other_schema_errors_have_no_task_details() {
+        let err = MeilisearchSchemaError::schema("No index settings provided");
+        assert!(err.task_error().is_none());
+        assert_eq!(err.meilisearch_code(), None);
+    }
+
+    #[test]
+    fn forgetting_max_total_hits_only_drops_that_index() {
+        let client = Client::new("http://localhost:7700", None::<String>).unwrap();
+        let cache = MAX_TOTAL_HITS.get_or_init(Default::default);
+        cache.lock().unwrap().extend([
+            (
+                max_total_hits_key(&client, "forget_me"),
+                (Instant::now(), 1000),
+            ),
+            (
+                max_total_hits_key(&client, "keep_me"),
+                (Instant::now(), 2000),
+            ),
+        ]);
+
+        forget_max_total_hits(&client, "forget_me");
+        assert_eq!(
+            cached_max_total_hits(&max_total_hits_key(&client, "forget_me")),
+            None
+        );
+        assert_eq!(
+            cached_max_total_hits(&max_total_hits_key(&client, "keep_me")),
+            Some(2000)
+        );
+    }
+
+    #[test]
+    fn cached_max_total_hits_expire() {
+        let client = Client::new("http://localhost:7700", None::<String>).unwrap();
+        let key = max_total_hits_key(&client, "expired");
+        let fetched_at = Instant::now() - MAX_TOTAL_HITS_TTL;
+        MAX_TOTAL_HITS
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap()
+            .insert(key.clone(), (fetched_at, 1000));
+
+        assert_eq!(cached_max_total_hits(&key), None);
+    }
+
+    #[test]
+    fn index_already_exists_is_detected_by_code_not_message() {
+        let err = meilisearch_error(ErrorCode::IndexAlreadyExists, "reworded by a new server");
+        assert!(is_index_already_exists(&err));
+
+        let err = meilisearch_error(ErrorCode::IndexNotFound, "index_already_exists");
+        assert!(!is_index_already_exists(&err));
+    }
+
+    #[test]
+    fn fully_populated_profile_round_trips_through_document() {
+        let profile = UserProfile::fully_populated();
+        let doc = UserProfileDocument::from(profile.clone());
+        assert_eq!(UserProfile::from(doc), profile);
+    }
+
+    #[test]
+    fn fully_populated_profile_round_trips_through_json() {
+        let profile = UserProfile::fully_populated();
+        assert_eq!(UserProfile::from_json(profile.to_json()).unwrap(), profile);
+    }
+
+    #[test]
+    fn normalizing_derives_lowercase_username() {
+        let doc: UserProfileDocument =
+            serde_json::from_value(serde_json::json!({"id": "1", "username": "Alice"})).unwrap();
+        assert_eq!(doc.username_normalized, None);
+
+        let doc = doc.normalized();
+        assert_eq!(doc.username.as_deref(), Some("Alice"));
+        assert_eq!(
+            doc.username_normalized.as_deref(),
+            Some(normalize_username("ALICE").as_str())
+        );
+    }
+
+    #[test]
+    fn normalizing_lowercases_wallet_addresses_and_keeps_the_originals() {
+        let checksummed = "0x52908400098527886E0F7030069857D2E4169EE7";
+        let mut doc = UserProfileDocument::from(
+            UserProfile::builder(1)
+                .wallet_addresses([checksummed])
+                .build(),
+        );
+        assert_eq!(doc.wallet_addresses, [checksummed]);
+        assert_eq!(
+            doc.wallet_addresses_normalized,
+            [normalize_wallet_address(checksummed)]
+        );
+
+        doc.wallet_addresses_normalized.clear();
+        assert_eq!(
+            doc.normalized().wallet_addresses_normalized,
+            [checksummed.to_lowercase()]
+        );
+    }
+
+    #[test]
+    fn normalizing_clears_stale_username() {
+        let mut doc = UserProfileDocument::from(UserProfile::builder(1).username("alice").build());
+        doc.username = None;
+        assert_eq!(doc.normalized().username_normalized, None);
+    }
+
+    #[test]
+    fn verified_filter_only_uses_filterable_attributes() {
+        let schema = get_user_profile_schema();
+        let filterable = schema.filterable().unwrap_or_default();
+        let filter = crate::helpers::filter::ProfileFilter::new().verified_only();
+        for field in filter_fields(&filter.build()) {
+            assert!(filterable.contains(&field), "{} is not filterable", field);
+        }
+    }
+
+    #[test]
+    fn verified_defaults_to_false_for_existing_documents() {
+        let doc: UserProfileDocument =
+            serde_json::from_value(serde_json::json!({"id": "1", "fid": 1})).unwrap();
+        assert!(!doc.verified);
+        assert!(!UserProfile::from(doc).verified);
+    }
+
+    #[test]
+    fn composite_sort_accepts_sortable_fields_in_any_order() {
+        check_sort(&["verified:desc", "follower_count:desc", "updated_at:desc"]).unwrap();
+        check_sort(&["updated_at:asc", "verified:asc"]).unwrap();
+        check_sort(&["_geoPoint(52.52, 13.405):asc", "fid:asc"]).unwrap();
+    }
+
+    #[test]
+    fn composite_sort_rejects_any_unsortable_key() {
+        let err = check_sort(&["verified:desc", "bio:desc"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Failed to apply schema: field 'bio' is not sortable"
+        );
+
+        let err = check_sort(&["verified:desc", "follower_count"]).unwrap_err();
+        assert!(err.to_string().contains("expected 'field:asc'"), "{}", err);
+    }
+
+    #[test]
+    fn predefined_schema_is_valid() {
+        validate_schema(&get_user_profile_schema()).unwrap();
+    }
+
+    #[test]
+    fn builder_schema_with_custom_fields_is_valid() {
+        let schema = UserProfileSchema::builder()
+            .add_searchable("custom_field")
+            .add_filterable("team")
+            .add_sortable("score")
+            .set_ranking([RankingRule::Words, RankingRule::Desc("score".to_string())])
+            .build();
+        assert_eq!(schema.custom_fields, ["custom_field", "team", "score"]);
+        validate_schema(&schema).unwrap();
+    }
+
+    #[test]
+    fn builder_records_each_custom_field_once() {
+        let schema = UserProfileSchema::builder()
+            .add_filterable("team")
+            .add_sortable("team")
+            .set_searchable(["username", "team"])
+            .build();
+        assert_eq!(schema.custom_fields, ["team"]);
+    }
+
+    #[test]
+    fn undeclared_attributes_are_rejected() {
+        let mut schema = get_user_profile_schema();
+        schema
+            .searchable
+            .get_or_insert_with(Default::default)
+            .attributes
+            .push("custom_field".to_string());
+        let err = validate_schema(&schema).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Failed to apply schema: Unknown searchable attribute 'custom_field'"
+        );
+    }
+
+    #[test]
+    fn camel_case_document_keys_are_accepted() {
+        let doc: UserProfileDocument = serde_json::from_value(serde_json::json!({
+            "id": "1",
+            "fid": 1,
+            "displayName": "Alice",
+            "pfpUrl": "https://example.com/alice.png",
+            "updatedAt": 1_646_092_800,
+            "followerCount": 10,
+            "followingCount": 5,
+            "createdAt": "2021-01-01T00:00:00Z",
+            "accountType": "bot",
+        }))
+        .unwrap();
+
+        assert_eq!(doc.display_name.as_deref(), Some("Alice"));
+        assert_eq!(
+            doc.pfp_url.as_deref(),
+            Some("https://example.com/alice.png")
+        );
+        assert_eq!(doc.updated_at, 1_646_092_800);
+        assert_eq!(doc.follower_count, 10);
+        assert_eq!(doc.following_count, 5);
+        assert_eq!(doc.created_at, 1_609_459_200);
+        assert_eq!(doc.account_type.as_deref(), Some("bot"));
+        assert!(doc.extra.is_empty());
+    }
+
+    #[test]
+    fn unknown_fields_are_kept_but_hit_metadata_is_not() {
+        let doc: UserProfileDocument = serde_json::from_value(serde_json::json!({
+            "id": "1",
+            "fid": 1,
+            "_geo": {"lat": 52.52, "lng": 13.405},
+            "nickname": "Al",
+            "_geoDistance": 12,
+            "_rankingScore": 0.9,
+            "_vectors": {"default": [0.1, 0.2]},
+            "_formatted": {"id": "1"},
+        }))
+        .unwrap();
+
+        assert!(doc.geo.is_some());
+        assert_eq!(
+            doc.extra,
+            HashMap::from([("nickname".to_string(), serde_json::json!("Al"))])
+        );
+        let json = serde_json::to_value(&doc).unwrap();
+        assert_eq!(json["nickname"], "Al");
+        assert!(json.get("_rankingScore").is_none());
+    }
+
+    #[test]
+    fn updated_at_round_trips_through_document() {
+        for secs in [
+            0,
+            1,
+            9,
+            10_000,
+            1_646_092_800,
+            u64::from(u32::MAX) + 1,
+            FAR_FUTURE,
+        ] {
+            let profile = UserProfile::builder(1).updated_at(secs).build();
+            let round_tripped = UserProfile::from(UserProfileDocument::from(profile.clone()));
+            assert_eq!(round_tripped, profile, "updated_at {}", secs);
+        }
+    }
+
+    #[test]
+    fn updated_at_round_trips_through_stored_json() {
+        for secs in [0, 1_646_092_800, FAR_FUTURE] {
+            let profile = UserProfile::builder(1).updated_at(secs).build();
+            let json = serde_json::to_string(&UserProfileDocument::from(profile.clone())).unwrap();
+            let doc: UserProfileDocument = serde_json::from_str(&json).unwrap();
+            assert_eq!(UserProfile::from(doc), profile, "updated_at {}", secs);
+        }
+    }
+
+    #[test]
+    fn rfc3339_timestamps_parse_to_whole_seconds() {
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(parse_timestamp("2022-03-01T00:00:00Z"), Some(1_646_092_800));
+        assert_eq!(
+            parse_timestamp("2022-03-01T00:00:00.999Z"),
+            Some(1_646_092_800)
+        );
+        assert_eq!(
+            parse_timestamp("2022-03-01T01:00:00+0100"),
+            Some(1_646_092_800)
+        );
+        assert_eq!(
+            parse_timestamp("9999-12-31T23:59:59Z"),
+            Some(253_402_300_799)
+        );
+        assert_eq!(parse_timestamp("1969-12-31T23:59:59Z"), None);
     }
 }
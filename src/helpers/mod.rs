@@ -1,3 +1,13 @@
 //! Helper functions for working with schemas
 
+pub mod backend;
+#[cfg(feature = "meilisearch")]
+pub mod bulk;
+pub mod common;
+pub mod filter;
+#[cfg(feature = "meilisearch")]
 pub mod meilisearch;
+pub mod profile;
+pub mod schema;
+#[cfg(feature = "meilisearch")]
+pub mod service;
@@ -0,0 +1,492 @@
+//! Convenience constructors and comparisons for user profiles
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::proto::meilisearch::{AccountType, GeoPoint, UserProfile};
+
+impl UserProfile {
+    /// A fully populated profile for examples and tests
+    ///
+    /// Every field is derived from `fid`, so profiles built from different
+    /// fids never collide.
+    pub fn example(fid: u64) -> Self {
+        UserProfileBuilder::new(fid)
+            .display_name(format!("User {}", fid))
+            .username(format!("user{}", fid))
+            .pfp_url(format!("https://example.com/pfp/{}.png", fid))
+            .bio(format!("Example profile for fid {}", fid))
+            .url(format!("https://example.com/users/{}", fid))
+            .location("Internet")
+            .twitter(format!("user{}", fid))
+            .github(format!("user{}", fid))
+            .updated_at(1646092800 + fid)
+            .created_at(1609459200 + fid)
+            .build()
+    }
+
+    /// A profile with every field set to a non-default value
+    ///
+    /// Built without `..` so that a new proto field fails to compile here
+    /// until the tests using it cover it.
+    #[cfg(test)]
+    pub(crate) fn fully_populated() -> Self {
+        Self {
+            id: "profile-42".to_string(),
+            fid: 42,
+            display_name: Some("Alice".to_string()),
+            username: Some("alice".to_string()),
+            pfp_url: Some("https://example.com/alice.png".to_string()),
+            bio: Some("Building things".to_string()),
+            url: Some("https://example.com/alice".to_string()),
+            location: Some("Berlin".to_string()),
+            twitter: Some("alice_tw".to_string()),
+            github: Some("alice_gh".to_string()),
+            updated_at: 1_646_092_800,
+            geo: Some(GeoPoint {
+                lat: 52.52,
+                lng: 13.405,
+            }),
+            verified: true,
+            follower_count: 1200,
+            following_count: 300,
+            created_at: 1_609_459_200,
+            account_type: AccountType::Organization.into(),
+            wallet_addresses: vec!["0x52908400098527886E0F7030069857D2E4169EE7".to_string()],
+        }
+    }
+
+    /// Start building a profile for `fid`
+    pub fn builder(fid: u64) -> UserProfileBuilder {
+        UserProfileBuilder::new(fid)
+    }
+}
+
+/// Builder for [`UserProfile`] with chainable setters
+///
+/// Unset optional fields stay `None`, and `id` defaults to the fid.
+#[derive(Debug, Clone, Default)]
+pub struct UserProfileBuilder {
+    id: Option<String>,
+    profile: UserProfile,
+}
+
+impl UserProfileBuilder {
+    /// Start a profile for `fid`
+    pub fn new(fid: u64) -> Self {
+        Self {
+            id: None,
+            profile: UserProfile {
+                fid,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Document id; defaults to `fid.to_string()`
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    pub fn display_name(mut self, display_name: impl Into<String>) -> Self {
+        self.profile.display_name = Some(display_name.into());
+        self
+    }
+
+    pub fn username(mut self, username: impl Into<String>) -> Self {
+        self.profile.username = Some(username.into());
+        self
+    }
+
+    pub fn pfp_url(mut self, pfp_url: impl Into<String>) -> Self {
+        self.profile.pfp_url = Some(pfp_url.into());
+        self
+    }
+
+    pub fn bio(mut self, bio: impl Into<String>) -> Self {
+        self.profile.bio = Some(bio.into());
+        self
+    }
+
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.profile.url = Some(url.into());
+        self
+    }
+
+    pub fn location(mut self, location: impl Into<String>) -> Self {
+        self.profile.location = Some(location.into());
+        self
+    }
+
+    pub fn twitter(mut self, twitter: impl Into<String>) -> Self {
+        self.profile.twitter = Some(twitter.into());
+        self
+    }
+
+    pub fn github(mut self, github: impl Into<String>) -> Self {
+        self.profile.github = Some(github.into());
+        self
+    }
+
+    /// Last update time in epoch seconds
+    pub fn updated_at(mut self, updated_at: u64) -> Self {
+        self.profile.updated_at = updated_at;
+        self
+    }
+
+    /// Account creation time in epoch seconds
+    pub fn created_at(mut self, created_at: u64) -> Self {
+        self.profile.created_at = created_at;
+        self
+    }
+
+    /// Whether the profile has a verified badge
+    pub fn verified(mut self, verified: bool) -> Self {
+        self.profile.verified = verified;
+        self
+    }
+
+    pub fn follower_count(mut self, follower_count: u64) -> Self {
+        self.profile.follower_count = follower_count;
+        self
+    }
+
+    pub fn following_count(mut self, following_count: u64) -> Self {
+        self.profile.following_count = following_count;
+        self
+    }
+
+    pub fn account_type(mut self, account_type: AccountType) -> Self {
+        self.profile.set_account_type(account_type);
+        self
+    }
+
+    /// Connected wallet addresses, replacing any set before
+    pub fn wallet_addresses(
+        mut self,
+        wallet_addresses: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.profile.wallet_addresses = wallet_addresses.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Location in decimal degrees
+    pub fn geo(mut self, lat: f64, lng: f64) -> Self {
+        self.profile.geo = Some(GeoPoint { lat, lng });
+        self
+    }
+
+    /// Finish the profile, filling `id` from the fid if it wasn't set
+    pub fn build(self) -> UserProfile {
+        let Self { id, mut profile } = self;
+        profile.id = id.unwrap_or_else(|| profile.fid.to_string());
+        profile
+    }
+}
+
+impl AccountType {
+    /// Lowercase name used in search documents and filters, e.g. `bot`
+    pub fn as_lowercase_str(&self) -> &'static str {
+        match self {
+            Self::Unspecified => "unspecified",
+            Self::Human => "human",
+            Self::Bot => "bot",
+            Self::Organization => "organization",
+        }
+    }
+}
+
+impl fmt::Display for AccountType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_lowercase_str())
+    }
+}
+
+impl From<&str> for AccountType {
+    /// Parse a lowercase name, case-insensitively; anything unrecognized is
+    /// `Unspecified`
+    fn from(name: &str) -> Self {
+        match name.to_lowercase().as_str() {
+            "human" => Self::Human,
+            "bot" => Self::Bot,
+            "organization" => Self::Organization,
+            _ => Self::Unspecified,
+        }
+    }
+}
+
+/// A user profile in the shape of the external HTTP API
+///
+/// Fields are camelCase, with `id` and `fid` exposed as `profileId` and
+/// `farcasterId`, and the account type as its lowercase name. Converts to and
+/// from [`UserProfile`] without loss, except that an account type the proto
+/// doesn't define becomes `unspecified`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct UserProfileApiView {
+    pub profile_id: String,
+    pub farcaster_id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pfp_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bio: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub twitter: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub github: Option<String>,
+    pub updated_at: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub geo: Option<GeoPoint>,
+    pub verified: bool,
+    pub follower_count: u64,
+    pub following_count: u64,
+    pub created_at: u64,
+    pub account_type: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub wallet_addresses: Vec<String>,
+}
+
+impl From<UserProfile> for UserProfileApiView {
+    fn from(profile: UserProfile) -> Self {
+        let account_type = profile.account_type().to_string();
+
+        // Destructure without `..` so new proto fields must be mapped here
+        let UserProfile {
+            id,
+            fid,
+            display_name,
+            username,
+            pfp_url,
+            bio,
+            url,
+            location,
+            twitter,
+            github,
+            updated_at,
+            geo,
+            verified,
+            follower_count,
+            following_count,
+            created_at,
+            account_type: _,
+            wallet_addresses,
+        } = profile;
+
+        Self {
+            profile_id: id,
+            farcaster_id: fid,
+            display_name,
+            username,
+            pfp_url,
+            bio,
+            url,
+            location,
+            twitter,
+            github,
+            updated_at,
+            geo,
+            verified,
+            follower_count,
+            following_count,
+            created_at,
+            account_type,
+            wallet_addresses,
+        }
+    }
+}
+
+impl From<UserProfileApiView> for UserProfile {
+    fn from(view: UserProfileApiView) -> Self {
+        let UserProfileApiView {
+            profile_id,
+            farcaster_id,
+            display_name,
+            username,
+            pfp_url,
+            bio,
+            url,
+            location,
+            twitter,
+            github,
+            updated_at,
+            geo,
+            verified,
+            follower_count,
+            following_count,
+            created_at,
+            account_type,
+            wallet_addresses,
+        } = view;
+
+        Self {
+            id: profile_id,
+            fid: farcaster_id,
+            display_name,
+            username,
+            pfp_url,
+            bio,
+            url,
+            location,
+            twitter,
+            github,
+            updated_at,
+            geo,
+            verified,
+            follower_count,
+            following_count,
+            created_at,
+            account_type: AccountType::from(account_type.as_str()).into(),
+            wallet_addresses,
+        }
+    }
+}
+
+/// Changes needed to bring one set of profiles in line with another
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProfileDiff {
+    /// Profiles that are new or differ in any field, in `incoming` order
+    pub to_upsert: Vec<UserProfile>,
+    /// Ids of profiles missing from `incoming`, in `current` order
+    pub to_delete: Vec<String>,
+}
+
+impl ProfileDiff {
+    /// Whether the two sets were already identical
+    pub fn is_empty(&self) -> bool {
+        self.to_upsert.is_empty() && self.to_delete.is_empty()
+    }
+}
+
+/// Compare the profiles currently stored with an incoming snapshot, by id
+///
+/// A profile is upserted when its id is new or when any field differs,
+/// including `updated_at`. If an id appears more than once in either set, the
+/// last occurrence is used.
+pub fn diff_user_profiles(current: &[UserProfile], incoming: &[UserProfile]) -> ProfileDiff {
+    let current_by_id: HashMap<&str, &UserProfile> =
+        current.iter().map(|p| (p.id.as_str(), p)).collect();
+    let incoming_by_id: HashMap<&str, &UserProfile> =
+        incoming.iter().map(|p| (p.id.as_str(), p)).collect();
+
+    let mut seen = HashSet::new();
+    let to_upsert = incoming
+        .iter()
+        .rev()
+        .filter(|p| seen.insert(p.id.as_str()))
+        .filter(|p| current_by_id.get(p.id.as_str()) != Some(p))
+        .cloned()
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+
+    let mut seen = HashSet::new();
+    let to_delete = current
+        .iter()
+        .filter(|p| !incoming_by_id.contains_key(p.id.as_str()))
+        .filter(|p| seen.insert(p.id.as_str()))
+        .map(|p| p.id.clone())
+        .collect();
+
+    ProfileDiff {
+        to_upsert,
+        to_delete,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_round_trips_through_api_view() {
+        let profile = UserProfile::fully_populated();
+        let view = UserProfileApiView::from(profile.clone());
+        assert_eq!(view.profile_id, "profile-42");
+        assert_eq!(view.farcaster_id, 42);
+        assert_eq!(view.account_type, "organization");
+        assert_eq!(UserProfile::from(view), profile);
+    }
+
+    #[test]
+    fn api_view_round_trips_through_profile() {
+        let view = UserProfileApiView::from(UserProfile::fully_populated());
+        assert_eq!(
+            UserProfileApiView::from(UserProfile::from(view.clone())),
+            view
+        );
+    }
+
+    #[test]
+    fn unknown_account_types_become_unspecified() {
+        let view = UserProfileApiView {
+            account_type: "alien".to_string(),
+            ..UserProfileApiView::default()
+        };
+        assert_eq!(
+            UserProfile::from(view).account_type(),
+            AccountType::Unspecified
+        );
+
+        let profile = UserProfile {
+            account_type: 99,
+            ..UserProfile::default()
+        };
+        assert_eq!(
+            UserProfileApiView::from(profile).account_type,
+            "unspecified"
+        );
+    }
+
+    #[test]
+    fn account_types_parse_case_insensitively() {
+        let view = UserProfileApiView {
+            account_type: "Organization".to_string(),
+            ..UserProfileApiView::default()
+        };
+        assert_eq!(
+            UserProfile::from(view).account_type(),
+            AccountType::Organization
+        );
+    }
+
+    #[test]
+    fn api_view_deserializes_camel_case_keys() {
+        let view: UserProfileApiView = serde_json::from_value(serde_json::json!({
+            "profileId": "p1",
+            "farcasterId": 42,
+            "displayName": "Alice",
+            "followerCount": 7,
+            "accountType": "human",
+        }))
+        .unwrap();
+
+        assert_eq!(view.profile_id, "p1");
+        assert_eq!(view.farcaster_id, 42);
+        assert_eq!(view.display_name.as_deref(), Some("Alice"));
+        assert_eq!(view.follower_count, 7);
+        assert_eq!(view.account_type, "human");
+    }
+
+    #[test]
+    fn api_view_serializes_external_field_names() {
+        let json =
+            serde_json::to_value(UserProfileApiView::from(UserProfile::example(42))).unwrap();
+
+        assert_eq!(json["profileId"], "42");
+        assert_eq!(json["farcasterId"], 42);
+        assert!(json.get("id").is_none());
+        assert!(json.get("fid").is_none());
+    }
+}
@@ -0,0 +1,96 @@
+//! Backend-agnostic description of an index schema
+
+use crate::proto::meilisearch::user_profile_schema::IndexSettings;
+use crate::proto::meilisearch::{
+    EmbedderSettings, FacetingSettings, PaginationSettings, TypoToleranceSettings,
+    UserProfileSchema,
+};
+
+/// A document schema that can be applied to a search index
+///
+/// Attribute lists return `None` when the schema leaves that setting
+/// untouched, and `Some` (possibly empty) when it should be applied.
+pub trait SchemaDefinition {
+    /// Index name and primary key
+    fn index_settings(&self) -> Option<&IndexSettings>;
+
+    /// Attributes used for full-text search, in order of importance
+    fn searchable(&self) -> Option<&[String]>;
+
+    /// Ranking rules, in order of application
+    fn ranking(&self) -> Option<&[String]>;
+
+    /// Attribute used to deduplicate search results
+    fn distinct_attribute(&self) -> Option<&str>;
+
+    /// Attributes that can be used in filter expressions
+    fn filterable(&self) -> Option<&[String]>;
+
+    /// Attributes that can be used for sorting
+    fn sortable(&self) -> Option<&[String]>;
+
+    /// Pagination limits
+    fn pagination(&self) -> Option<&PaginationSettings>;
+
+    /// Faceting limits
+    fn faceting(&self) -> Option<&FacetingSettings>;
+
+    /// Typo tolerance thresholds and exact-match attributes
+    fn typo_tolerance(&self) -> Option<&TypoToleranceSettings>;
+
+    /// Embedders for vector search; empty leaves the index's embedders untouched
+    fn embedders(&self) -> &[EmbedderSettings];
+
+    /// Version of the schema definition; 0 means unversioned
+    fn schema_version(&self) -> u32;
+}
+
+impl SchemaDefinition for UserProfileSchema {
+    fn index_settings(&self) -> Option<&IndexSettings> {
+        self.index.as_ref()
+    }
+
+    fn searchable(&self) -> Option<&[String]> {
+        self.searchable.as_ref().map(|s| s.attributes.as_slice())
+    }
+
+    fn ranking(&self) -> Option<&[String]> {
+        self.ranking.as_ref().map(|r| r.rules.as_slice())
+    }
+
+    fn distinct_attribute(&self) -> Option<&str> {
+        if self.distinct_attribute.is_empty() {
+            None
+        } else {
+            Some(&self.distinct_attribute)
+        }
+    }
+
+    fn filterable(&self) -> Option<&[String]> {
+        self.filterable.as_ref().map(|f| f.attributes.as_slice())
+    }
+
+    fn sortable(&self) -> Option<&[String]> {
+        self.sortable.as_ref().map(|s| s.attributes.as_slice())
+    }
+
+    fn pagination(&self) -> Option<&PaginationSettings> {
+        self.pagination.as_ref()
+    }
+
+    fn faceting(&self) -> Option<&FacetingSettings> {
+        self.faceting.as_ref()
+    }
+
+    fn typo_tolerance(&self) -> Option<&TypoToleranceSettings> {
+        self.typo_tolerance.as_ref()
+    }
+
+    fn embedders(&self) -> &[EmbedderSettings] {
+        &self.embedders
+    }
+
+    fn schema_version(&self) -> u32 {
+        self.schema_version
+    }
+}
@@ -0,0 +1,290 @@
+//! A shareable handle bundling a Meilisearch client with the profile helpers
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use meilisearch_sdk::{client::Client, task_info::TaskInfo};
+
+use crate::helpers::backend::{ProfileQuery, ProfileSearchBackend};
+use crate::helpers::common::Fid;
+use crate::helpers::filter::ProfileFilter;
+use crate::helpers::meilisearch::{
+    self as helpers, BatchReport, IndexStats, MeilisearchSchemaError, SchemaChangeReport,
+    ScoredUserProfile, SearchOptions, UserProfileDocument,
+};
+use crate::proto::meilisearch::{UserProfile, UserProfileSchema};
+
+/// The user profile helpers as methods on one shared client
+///
+/// Every helper only needs `&Client`, and the client is `Send + Sync`, so it
+/// never needs a `Mutex`: clone this service into each task instead. Cloning
+/// only bumps a reference count. The SDK `Client` is cheap to clone too: its
+/// HTTP client is reference-counted, so a clone just copies the host and API
+/// key. The service only saves passing the client to every call.
+#[derive(Debug, Clone)]
+pub struct SchemaService {
+    client: Arc<Client>,
+}
+
+impl SchemaService {
+    /// Wrap a client
+    pub fn new(client: Client) -> Self {
+        Self::from(Arc::new(client))
+    }
+
+    /// The underlying client, for helpers that aren't exposed as methods
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// See [`helpers::apply_user_profile_schema`]
+    pub async fn apply_user_profile_schema(&self) -> Result<(), MeilisearchSchemaError> {
+        helpers::apply_user_profile_schema(&self.client).await
+    }
+
+    /// See [`helpers::apply_user_profile_schema_with`]
+    pub async fn apply_user_profile_schema_with(
+        &self,
+        schema: &UserProfileSchema,
+    ) -> Result<(), MeilisearchSchemaError> {
+        helpers::apply_user_profile_schema_with(&self.client, schema).await
+    }
+
+    /// See [`helpers::apply_user_profile_schema_when_ready`]
+    pub async fn apply_user_profile_schema_when_ready(
+        &self,
+        timeout: Duration,
+    ) -> Result<(), MeilisearchSchemaError> {
+        helpers::apply_user_profile_schema_when_ready(&self.client, timeout).await
+    }
+
+    /// See [`helpers::ensure_user_profile_schema`]
+    pub async fn ensure_user_profile_schema(
+        &self,
+    ) -> Result<SchemaChangeReport, MeilisearchSchemaError> {
+        helpers::ensure_user_profile_schema(&self.client).await
+    }
+
+    /// See [`helpers::current_schema_version`]
+    pub async fn current_schema_version(&self) -> Result<u32, MeilisearchSchemaError> {
+        helpers::current_schema_version(&self.client).await
+    }
+
+    /// See [`helpers::user_profile_index_stats`]
+    pub async fn user_profile_index_stats(&self) -> Result<IndexStats, MeilisearchSchemaError> {
+        helpers::user_profile_index_stats(&self.client).await
+    }
+
+    /// See [`helpers::wait_until_indexed`]
+    pub async fn wait_until_indexed(
+        &self,
+        timeout: Duration,
+    ) -> Result<(), MeilisearchSchemaError> {
+        helpers::wait_until_indexed(&self.client, timeout).await
+    }
+
+    /// See [`helpers::await_task`]
+    pub async fn await_task(&self, task: TaskInfo) -> Result<(), MeilisearchSchemaError> {
+        helpers::await_task(&self.client, task).await
+    }
+
+    /// See [`helpers::batch_create_user_profiles`]
+    pub async fn batch_create_user_profiles(
+        &self,
+        profiles: &[UserProfile],
+    ) -> Result<(), MeilisearchSchemaError> {
+        helpers::batch_create_user_profiles(&self.client, profiles).await
+    }
+
+    /// See [`helpers::batch_create_user_profiles_report`]
+    pub async fn batch_create_user_profiles_report(
+        &self,
+        profiles: &[UserProfile],
+    ) -> Result<BatchReport, MeilisearchSchemaError> {
+        helpers::batch_create_user_profiles_report(&self.client, profiles).await
+    }
+
+    /// See [`helpers::merge_user_profile`]
+    pub async fn merge_user_profile(
+        &self,
+        id: &str,
+        partial: UserProfileDocument,
+    ) -> Result<UserProfileDocument, MeilisearchSchemaError> {
+        helpers::merge_user_profile(&self.client, id, partial).await
+    }
+
+    /// See [`helpers::get_user_profiles_by_ids`]
+    pub async fn get_user_profiles_by_ids(
+        &self,
+        ids: &[&str],
+    ) -> Result<Vec<Option<UserProfile>>, MeilisearchSchemaError> {
+        helpers::get_user_profiles_by_ids(&self.client, ids).await
+    }
+
+    /// See [`helpers::get_user_profile_by_username`]
+    pub async fn get_user_profile_by_username(
+        &self,
+        username: &str,
+    ) -> Result<Vec<UserProfile>, MeilisearchSchemaError> {
+        helpers::get_user_profile_by_username(&self.client, username).await
+    }
+
+    /// See [`helpers::get_profile_by_wallet`]
+    pub async fn get_profile_by_wallet(
+        &self,
+        address: &str,
+    ) -> Result<Vec<UserProfile>, MeilisearchSchemaError> {
+        helpers::get_profile_by_wallet(&self.client, address).await
+    }
+
+    /// See [`helpers::existing_fids`]
+    pub async fn existing_fids(
+        &self,
+        fids: &[Fid],
+    ) -> Result<HashSet<Fid>, MeilisearchSchemaError> {
+        helpers::existing_fids(&self.client, fids).await
+    }
+
+    /// See [`helpers::search_user_profiles`]
+    pub async fn search_user_profiles(
+        &self,
+        query: &str,
+        limit: Option<usize>,
+        offset: Option<usize>,
+        filter: Option<&str>,
+    ) -> Result<Vec<UserProfile>, MeilisearchSchemaError> {
+        helpers::search_user_profiles(&self.client, query, limit, offset, filter).await
+    }
+
+    /// See [`helpers::search_user_profiles_with`]
+    pub async fn search_user_profiles_with(
+        &self,
+        query: &str,
+        options: &SearchOptions<'_>,
+    ) -> Result<Vec<UserProfile>, MeilisearchSchemaError> {
+        helpers::search_user_profiles_with(&self.client, query, options).await
+    }
+
+    /// See [`helpers::search_user_profiles_scored`]
+    pub async fn search_user_profiles_scored(
+        &self,
+        query: &str,
+        options: &SearchOptions<'_>,
+    ) -> Result<Vec<ScoredUserProfile>, MeilisearchSchemaError> {
+        helpers::search_user_profiles_scored(&self.client, query, options).await
+    }
+
+    /// See [`helpers::multi_search_user_profiles`]
+    pub async fn multi_search_user_profiles(
+        &self,
+        queries: &[&str],
+        limit_each: usize,
+    ) -> Result<Vec<Vec<UserProfile>>, MeilisearchSchemaError> {
+        helpers::multi_search_user_profiles(&self.client, queries, limit_each).await
+    }
+
+    /// See [`helpers::autocomplete_usernames`]
+    pub async fn autocomplete_usernames(
+        &self,
+        prefix: &str,
+        limit: usize,
+    ) -> Result<Vec<UserProfile>, MeilisearchSchemaError> {
+        helpers::autocomplete_usernames(&self.client, prefix, limit).await
+    }
+
+    /// See [`helpers::find_duplicate_profiles`]
+    pub async fn find_duplicate_profiles(
+        &self,
+        profile: &UserProfile,
+        threshold: f64,
+    ) -> Result<Vec<UserProfile>, MeilisearchSchemaError> {
+        helpers::find_duplicate_profiles(&self.client, profile, threshold).await
+    }
+
+    /// See [`helpers::delete_user_profiles_by_filter`]
+    pub async fn delete_user_profiles_by_filter(
+        &self,
+        filter: &str,
+    ) -> Result<TaskInfo, MeilisearchSchemaError> {
+        helpers::delete_user_profiles_by_filter(&self.client, filter).await
+    }
+}
+
+impl From<Arc<Client>> for SchemaService {
+    fn from(client: Arc<Client>) -> Self {
+        Self { client }
+    }
+}
+
+impl From<Client> for SchemaService {
+    fn from(client: Client) -> Self {
+        Self::new(client)
+    }
+}
+
+/// The Meilisearch helpers as a [`ProfileSearchBackend`]
+///
+/// Each method delegates to the matching helper and waits for any enqueued
+/// task to finish.
+#[async_trait]
+impl ProfileSearchBackend for SchemaService {
+    type Error = MeilisearchSchemaError;
+
+    // Waits for index creation and every settings task, as the trait requires
+    async fn apply_schema(&self) -> Result<(), Self::Error> {
+        self.apply_user_profile_schema().await
+    }
+
+    async fn upsert(&self, profiles: &[UserProfile]) -> Result<(), Self::Error> {
+        let documents = helpers::profiles_to_documents(profiles);
+        let task = helpers::add_user_profile_documents(&self.client, &documents).await?;
+        self.await_task(task).await
+    }
+
+    async fn search(&self, query: &ProfileQuery<'_>) -> Result<Vec<UserProfile>, Self::Error> {
+        // `fid IN []` matches nothing, so don't ask
+        if query.fids.is_some_and(|fids| fids.is_empty()) {
+            return Ok(Vec::new());
+        }
+
+        let filter = query
+            .fids
+            .map(|fids| ProfileFilter::new().fid_in(fids).build());
+        let options = SearchOptions {
+            limit: query.limit,
+            offset: query.offset,
+            filter: filter.as_deref(),
+            ..SearchOptions::default()
+        };
+        self.search_user_profiles_with(query.query, &options).await
+    }
+
+    async fn delete(&self, ids: &[&str]) -> Result<(), Self::Error> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let task = helpers::delete_user_profiles(&self.client, ids).await?;
+        self.await_task(task).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn searching_no_fids_finds_nothing_without_a_server() {
+        // Nothing listens here, so any request would fail
+        let client = Client::new("http://127.0.0.1:9", None::<String>).unwrap();
+        let service = SchemaService::new(client);
+        let query = ProfileQuery {
+            fids: Some(&[]),
+            ..ProfileQuery::new("alice")
+        };
+
+        assert_eq!(service.search(&query).await.unwrap(), Vec::new());
+    }
+}
@@ -16,6 +16,10 @@ pub mod proto {
 // Helper modules
 pub mod helpers;
 
+// In-memory backends for downstream tests
+#[cfg(feature = "testing")]
+pub mod testing;
+
 // Example usage:
 //
 // ```
@@ -0,0 +1,164 @@
+//! Test doubles and helpers for code written against the backend traits
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::{PoisonError, RwLock};
+#[cfg(feature = "meilisearch")]
+use std::time::Duration;
+
+use async_trait::async_trait;
+#[cfg(feature = "meilisearch")]
+use meilisearch_sdk::client::Client;
+
+use crate::helpers::backend::{ProfileQuery, ProfileSearchBackend};
+#[cfg(feature = "meilisearch")]
+use crate::helpers::meilisearch::{
+    get_user_profiles_by_ids, wait_until_indexed, MeilisearchSchemaError, INDEXING_POLL_INTERVAL,
+};
+use crate::proto::meilisearch::UserProfile;
+
+/// A [`ProfileSearchBackend`] that keeps profiles in memory
+///
+/// Search is a case-insensitive substring match over the text fields, with
+/// results ordered by id rather than relevance. This is only meant to exercise
+/// call-site logic without a running search server.
+#[derive(Debug, Default)]
+pub struct InMemoryProfileBackend {
+    profiles: RwLock<HashMap<String, UserProfile>>,
+}
+
+impl InMemoryProfileBackend {
+    /// Create an empty backend
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get a stored profile by id
+    pub fn get(&self, id: &str) -> Option<UserProfile> {
+        self.read(|profiles| profiles.get(id).cloned())
+    }
+
+    /// Number of stored profiles
+    pub fn len(&self) -> usize {
+        self.read(HashMap::len)
+    }
+
+    /// Whether no profiles are stored
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // A panic while holding the lock can't leave the map half-updated, so a
+    // poisoned lock is still safe to use
+    fn read<R>(&self, f: impl FnOnce(&HashMap<String, UserProfile>) -> R) -> R {
+        f(&self.profiles.read().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    fn write<R>(&self, f: impl FnOnce(&mut HashMap<String, UserProfile>) -> R) -> R {
+        f(&mut self
+            .profiles
+            .write()
+            .unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+/// Whether any text field of the profile contains the lowercased query
+fn matches_query(profile: &UserProfile, query: &str) -> bool {
+    if query.is_empty() {
+        return true;
+    }
+
+    let fields = [
+        &profile.username,
+        &profile.display_name,
+        &profile.bio,
+        &profile.location,
+        &profile.twitter,
+        &profile.github,
+    ];
+    fields
+        .into_iter()
+        .flatten()
+        .any(|value| value.to_lowercase().contains(query))
+}
+
+#[async_trait]
+impl ProfileSearchBackend for InMemoryProfileBackend {
+    type Error = Infallible;
+
+    async fn apply_schema(&self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    async fn upsert(&self, profiles: &[UserProfile]) -> Result<(), Self::Error> {
+        self.write(|stored| {
+            for profile in profiles {
+                stored.insert(profile.id.clone(), profile.clone());
+            }
+        });
+        Ok(())
+    }
+
+    async fn search(&self, query: &ProfileQuery<'_>) -> Result<Vec<UserProfile>, Self::Error> {
+        let text = query.query.trim().to_lowercase();
+        let mut hits: Vec<UserProfile> = self.read(|profiles| {
+            profiles
+                .values()
+                .filter(|profile| {
+                    query
+                        .fids
+                        .is_none_or(|fids| fids.iter().any(|fid| fid.as_u64() == profile.fid))
+                })
+                .filter(|profile| matches_query(profile, &text))
+                .cloned()
+                .collect()
+        });
+        hits.sort_by(|a, b| a.id.cmp(&b.id));
+
+        Ok(hits
+            .into_iter()
+            .skip(query.offset.unwrap_or(0))
+            .take(query.limit.unwrap_or(usize::MAX))
+            .collect())
+    }
+
+    async fn delete(&self, ids: &[&str]) -> Result<(), Self::Error> {
+        self.write(|stored| {
+            for id in ids {
+                stored.remove(*id);
+            }
+        });
+        Ok(())
+    }
+}
+
+/// Wait until the profile with `id` can be read back from Meilisearch
+///
+/// Waits for indexing to settle and then fetches the profile, retrying until
+/// it shows up. Fails if it hasn't within `timeout`, so a test can write a
+/// profile and then assert on it without sleeping for a guessed duration.
+#[cfg(feature = "meilisearch")]
+pub async fn assert_profile_indexed(
+    client: &Client,
+    id: &str,
+    timeout: Duration,
+) -> Result<UserProfile, MeilisearchSchemaError> {
+    let poll = async {
+        loop {
+            wait_until_indexed(client, timeout).await?;
+            if let Some(Some(profile)) = get_user_profiles_by_ids(client, &[id]).await?.pop() {
+                return Ok(profile);
+            }
+            tokio::time::sleep(INDEXING_POLL_INTERVAL).await;
+        }
+    };
+
+    match tokio::time::timeout(timeout, poll).await {
+        Ok(result) => result,
+        Err(_) => Err(MeilisearchSchemaError::Client(format!(
+            "profile '{}' was not indexed within {}ms",
+            id,
+            timeout.as_millis()
+        ))),
+    }
+}
@@ -0,0 +1,260 @@
+//! Tests against a running Meilisearch
+//!
+//! These are ignored by default. Point `MEILI_URL` (and `MEILI_MASTER_KEY` if
+//! the server needs one) at a disposable instance and run them one at a time,
+//! as they share the user profiles index:
+//!
+//! ```bash
+//! MEILI_URL=http://localhost:7700 cargo test -- --ignored --test-threads=1
+//! ```
+#![cfg(feature = "meilisearch")]
+
+use std::time::Duration;
+
+use chrono::{TimeZone, Utc};
+use meilisearch_sdk::client::Client;
+use waypoint_schemas::helpers::meilisearch::{
+    add_user_profile_documents, apply_user_profile_schema, await_task, batch_create_user_profiles,
+    batch_create_user_profiles_with, build_client_from_env, current_schema_version,
+    delete_all_user_profiles, delete_stale_user_profiles, dump_settings, get_profile_by_wallet,
+    get_user_profile_by_username, get_user_profiles_by_ids, restore_settings,
+    search_user_profiles_with, wait_until_indexed, BatchOptions, SearchOptions,
+    UserProfileDocument, USER_PROFILE_SCHEMA_VERSION,
+};
+use waypoint_schemas::proto::meilisearch::UserProfile;
+
+/// A client for a user profiles index with the schema applied and no documents
+async fn empty_index() -> Client {
+    let client = build_client_from_env().expect("MEILI_URL must point at a test server");
+    apply_user_profile_schema(&client).await.unwrap();
+    let task = delete_all_user_profiles(&client).await.unwrap();
+    await_task(&client, task).await.unwrap();
+    client
+}
+
+/// Write profiles and wait until searches see them
+///
+/// The batch task itself is awaited; the index only reports that it's
+/// indexing once the task is picked up, so polling that alone can return
+/// before the profiles are written.
+async fn index_profiles(client: &Client, profiles: &[UserProfile]) {
+    let options = BatchOptions { wait_indexed: true };
+    batch_create_user_profiles_with(client, profiles, &options)
+        .await
+        .unwrap();
+}
+
+fn ids(profiles: &[UserProfile]) -> Vec<&str> {
+    profiles.iter().map(|profile| profile.id.as_str()).collect()
+}
+
+#[tokio::test]
+#[ignore = "requires a running Meilisearch"]
+async fn sort_by_updated_at_is_numeric_across_magnitudes() {
+    let client = empty_index().await;
+    index_profiles(
+        &client,
+        &[
+            UserProfile::builder(1).updated_at(9).build(),
+            UserProfile::builder(2).updated_at(10_000).build(),
+            UserProfile::builder(3).updated_at(100).build(),
+        ],
+    )
+    .await;
+
+    let options = SearchOptions {
+        sort: Some(&["updated_at:desc"]),
+        ..SearchOptions::default()
+    };
+    let hits = search_user_profiles_with(&client, "", &options)
+        .await
+        .unwrap();
+    assert_eq!(ids(&hits), ["2", "3", "1"]);
+}
+
+#[tokio::test]
+#[ignore = "requires a running Meilisearch"]
+async fn applying_the_schema_twice_succeeds() {
+    let client = empty_index().await;
+
+    // The index exists now, so this must not fail trying to create it again
+    apply_user_profile_schema(&client).await.unwrap();
+    assert_eq!(
+        current_schema_version(&client).await.unwrap(),
+        USER_PROFILE_SCHEMA_VERSION
+    );
+}
+
+#[tokio::test]
+#[ignore = "requires a running Meilisearch"]
+async fn restoring_settings_clears_the_schema_version() {
+    let client = empty_index().await;
+    let dump = dump_settings(&client).await.unwrap();
+
+    let task = restore_settings(&client, &dump).await.unwrap();
+    await_task(&client, task).await.unwrap();
+    assert_eq!(current_schema_version(&client).await.unwrap(), 0);
+}
+
+#[tokio::test]
+#[ignore = "requires a running Meilisearch"]
+async fn username_lookup_ignores_case_for_raw_documents() {
+    let client = empty_index().await;
+
+    // Shaped like a dump made before `username_normalized` existed
+    let doc: UserProfileDocument =
+        serde_json::from_value(serde_json::json!({"id": "1", "fid": 1, "username": "Alice"}))
+            .unwrap();
+    let task = add_user_profile_documents(&client, &[doc]).await.unwrap();
+    await_task(&client, task).await.unwrap();
+
+    for username in ["alice", "ALICE", "aLiCe"] {
+        let found = get_user_profile_by_username(&client, username)
+            .await
+            .unwrap();
+        assert_eq!(ids(&found), ["1"], "looking up {}", username);
+        assert_eq!(found[0].username.as_deref(), Some("Alice"));
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires a running Meilisearch"]
+async fn composite_sort_breaks_ties_in_order() {
+    let client = empty_index().await;
+    index_profiles(
+        &client,
+        &[
+            UserProfile::builder(1)
+                .verified(false)
+                .follower_count(900)
+                .updated_at(5)
+                .build(),
+            UserProfile::builder(2)
+                .verified(true)
+                .follower_count(10)
+                .updated_at(1)
+                .build(),
+            UserProfile::builder(3)
+                .verified(true)
+                .follower_count(10)
+                .updated_at(2)
+                .build(),
+            UserProfile::builder(4)
+                .verified(true)
+                .follower_count(50)
+                .updated_at(1)
+                .build(),
+        ],
+    )
+    .await;
+
+    let options = SearchOptions {
+        sort: Some(&["verified:desc", "follower_count:desc", "updated_at:desc"]),
+        ..SearchOptions::default()
+    };
+    let hits = search_user_profiles_with(&client, "", &options)
+        .await
+        .unwrap();
+    assert_eq!(ids(&hits), ["4", "3", "2", "1"]);
+}
+
+#[tokio::test]
+#[ignore = "requires a running Meilisearch"]
+async fn only_profiles_updated_before_the_cutoff_are_deleted() {
+    let client = empty_index().await;
+    let cutoff = 1_700_000_000;
+    index_profiles(
+        &client,
+        &[
+            UserProfile::builder(1).updated_at(cutoff - 86_400).build(),
+            UserProfile::builder(2).updated_at(cutoff).build(),
+            UserProfile::builder(3).updated_at(cutoff + 1).build(),
+            UserProfile::builder(4).updated_at(0).build(),
+        ],
+    )
+    .await;
+
+    // The inserts must be processed before the delete is enqueued, or it
+    // would have nothing to delete
+    let options = SearchOptions {
+        sort: Some(&["fid:asc"]),
+        ..SearchOptions::default()
+    };
+    let indexed = search_user_profiles_with(&client, "", &options)
+        .await
+        .unwrap();
+    assert_eq!(ids(&indexed), ["1", "2", "3", "4"]);
+
+    let older_than = Utc.timestamp_opt(cutoff as i64, 0).unwrap();
+    let task = delete_stale_user_profiles(&client, older_than)
+        .await
+        .unwrap();
+    await_task(&client, task).await.unwrap();
+
+    let remaining = search_user_profiles_with(&client, "", &options)
+        .await
+        .unwrap();
+    assert_eq!(ids(&remaining), ["2", "3"]);
+}
+
+#[tokio::test]
+#[ignore = "requires a running Meilisearch"]
+async fn wallet_lookup_ignores_checksum_casing() {
+    let client = empty_index().await;
+    let checksummed = "0x52908400098527886E0F7030069857D2E4169EE7";
+    index_profiles(
+        &client,
+        &[
+            UserProfile::builder(1)
+                .wallet_addresses([checksummed, "0xabc"])
+                .build(),
+            UserProfile::builder(2).wallet_addresses(["0xdef"]).build(),
+        ],
+    )
+    .await;
+
+    // Both inserts are processed, so a miss below can't be a pending write
+    let other = get_profile_by_wallet(&client, "0xDEF").await.unwrap();
+    assert_eq!(ids(&other), ["2"]);
+
+    for address in [checksummed, &checksummed.to_lowercase()] {
+        let found = get_profile_by_wallet(&client, address).await.unwrap();
+        assert_eq!(ids(&found), ["1"], "looking up {}", address);
+        assert_eq!(found[0].wallet_addresses, [checksummed, "0xabc"]);
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires a running Meilisearch"]
+async fn profiles_by_ids_are_positional() {
+    let client = empty_index().await;
+    index_profiles(&client, &[UserProfile::example(1), UserProfile::example(2)]).await;
+
+    let found = get_user_profiles_by_ids(&client, &["2", "missing", "1", "2"])
+        .await
+        .unwrap();
+    let found: Vec<Option<&str>> = found
+        .iter()
+        .map(|profile| profile.as_ref().map(|profile| profile.id.as_str()))
+        .collect();
+    assert_eq!(found, [Some("2"), None, Some("1"), Some("2")]);
+}
+
+#[tokio::test]
+#[ignore = "requires a running Meilisearch"]
+async fn waiting_until_indexed_covers_enqueued_writes() {
+    let client = empty_index().await;
+
+    // Returns as soon as the task is enqueued, before it's processing
+    batch_create_user_profiles(&client, &[UserProfile::example(1), UserProfile::example(2)])
+        .await
+        .unwrap();
+    wait_until_indexed(&client, Duration::from_secs(30))
+        .await
+        .unwrap();
+
+    let hits = search_user_profiles_with(&client, "", &SearchOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(hits.len(), 2);
+}